@@ -0,0 +1,65 @@
+//! Procedural macros for the Rust Peripheral Access Crate Generator.
+//!
+//! The [pac!] macro runs the same generation pipeline as the command line tool but
+//! expands the peripheral and register modules in place instead of writing a crate.
+
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Expands a register description into the peripheral access modules in place.
+///
+/// The path is resolved relative to the `Cargo.toml` of the crate using the macro.
+/// The crate must depend on `bit_field` and `volatile-register`, just like a generated crate.
+/// The optional items are gated on the `rt`, `cortex-m`, `defmt`, `serde` and `linux`
+/// features of the crate, which only need to be declared if they are used.
+///
+/// ```ignore
+/// rust_pac_gen_macros::pac!("resources/zynq-700.json");
+///
+/// let mode = uart::RegisterBlock::uart0().mode.read();
+/// ```
+#[proc_macro]
+pub fn pac(input: TokenStream) -> TokenStream {
+    let entry_file = parse_macro_input!(input as LitStr);
+
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let entry_path = manifest_dir.join(entry_file.value());
+
+    let pac = match rust_pac_gen::parse::parse_input(&entry_path) {
+        Ok(pac) => pac,
+        Err(e) => {
+            return syn::Error::new(entry_file.span(), e.to_string())
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let collisions = rust_pac_gen::gen::ident::find_collisions(&pac);
+    if !collisions.is_empty() {
+        return syn::Error::new(
+            entry_file.span(),
+            rust_pac_gen::Error::NameCollisions(collisions).to_string(),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // Reference every source file so the compiler rebuilds when the description changes
+    let source_files = pac
+        .source_files
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned());
+    let pac_tokens = rust_pac_gen::gen::gen_pac_tokens(&pac, &Default::default());
+
+    quote! {
+        #(const _: &[u8] = include_bytes!(#source_files);)*
+
+        #pac_tokens
+    }
+    .into()
+}
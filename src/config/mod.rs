@@ -1,19 +1,203 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use serde::Deserialize;
 
-use crate::defaults;
+use crate::{defaults, error::Error};
 
 #[derive(Deserialize, Debug)]
 pub struct ConfigOption {
     pub bit_field_version: Option<String>,
     pub volatile_register_version: Option<String>,
+    pub cortex_m_version: Option<String>,
+    pub critical_section_version: Option<String>,
+    pub defmt_version: Option<String>,
+    pub serde_version: Option<String>,
+    pub libc_version: Option<String>,
+    pub zero_dependencies: Option<bool>,
+    pub extension_files: Option<bool>,
+    pub svd2rust_api: Option<bool>,
+    pub atomic_helpers: Option<bool>,
+    pub debug_scripts: Option<bool>,
+    pub package: Option<PackageOption>,
+    pub naming: Option<NamingOption>,
+    pub dependencies: Option<toml::Table>,
+    pub features: Option<BTreeMap<String, Vec<String>>>,
 }
 
-#[derive(Debug)]
+/// The PackageOption struct represents the `[package]` table of the configuration file.
+/// The keys match the ones of the `[package]` table of a Cargo.toml.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageOption {
+    pub version: Option<String>,
+    pub edition: Option<String>,
+    pub authors: Option<Vec<String>>,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub rust_version: Option<String>,
+    pub metadata: Option<toml::Table>,
+}
+
+/// The NamingOption struct represents the `[naming]` table of the configuration file.
+#[derive(Deserialize, Debug)]
+pub struct NamingOption {
+    pub register_suffix: Option<String>,
+    pub builder_suffix: Option<String>,
+    pub setter_prefix: Option<String>,
+    pub peripheral_accessor: Option<String>,
+    pub strip_prefixes: Option<bool>,
+    pub rename: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Config {
     pub bit_field_version: String,
     pub volatile_register_version: String,
+    /// The version of the optional cortex-m dependency, used when the device has interrupts
+    pub cortex_m_version: String,
+    /// The version of the optional critical-section dependency, used by the atomic helpers
+    pub critical_section_version: String,
+    /// The version of the optional defmt dependency, used to log the register builders
+    pub defmt_version: String,
+    /// The version of the optional serde dependency, used to (de)serialize the register builders
+    pub serde_version: String,
+    /// The version of the optional libc dependency, used to map the registers under Linux
+    pub libc_version: String,
+    /// Generate a crate without dependencies, with the volatile and bit manipulation code inlined
+    pub zero_dependencies: bool,
+    /// Generate and include a hand-written `ext.rs` file in every peripheral module
+    pub extension_files: bool,
+    /// Generate svd2rust-style `R`/`W` reader and writer proxies next to the register builders
+    pub svd2rust_api: bool,
+    /// Generate atomic set/clear/toggle helpers for the read-write registers
+    pub atomic_helpers: bool,
+    /// Generate OpenOCD and GDB scripts that print the decoded registers of a running target
+    pub debug_scripts: bool,
+    /// The `[package]` metadata of the generated Cargo.toml
+    pub package: Package,
+    /// The naming conventions of the generated code
+    pub naming: Naming,
+    /// Extra dependencies added to the generated Cargo.toml, as written in the configuration file
+    pub dependencies: toml::Table,
+    /// Extra features added to the generated Cargo.toml
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+/// The Package struct contains the `[package]` metadata of the generated Cargo.toml.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub version: String,
+    pub edition: String,
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub rust_version: Option<String>,
+    pub metadata: Option<toml::Table>,
+}
+
+/// The Naming struct contains the naming conventions of the generated code.
+#[derive(Debug, Clone)]
+pub struct Naming {
+    /// Appended to the name of a register for its register struct, e.g. `ModeRegister`
+    pub register_suffix: String,
+    /// Appended to the name of a register for its builder, e.g. `ModeBuilder`
+    pub builder_suffix: String,
+    /// Prepended to the name of a field for its builder setter, e.g. `with_char_len`
+    pub setter_prefix: String,
+    /// The name of the RegisterBlock constructor of a peripheral, where `{name}` is
+    /// replaced with the snake case name of the peripheral, e.g. `uart0`
+    pub peripheral_accessor: String,
+    /// Strip the name of the peripheral from the start of its register and field names,
+    /// and the name of the register from the start of its field names
+    pub strip_prefixes: bool,
+    /// New names for peripherals, clusters, registers and fields, by their path in the
+    /// description (e.g. `UART.MODE.char_len`)
+    pub rename: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bit_field_version: defaults::BIT_FIELD_VERSION.to_string(),
+            volatile_register_version: defaults::VOLATILE_REGISTER_VERSION.to_string(),
+            cortex_m_version: defaults::CORTEX_M_VERSION.to_string(),
+            critical_section_version: defaults::CRITICAL_SECTION_VERSION.to_string(),
+            defmt_version: defaults::DEFMT_VERSION.to_string(),
+            serde_version: defaults::SERDE_VERSION.to_string(),
+            libc_version: defaults::LIBC_VERSION.to_string(),
+            zero_dependencies: false,
+            extension_files: false,
+            svd2rust_api: false,
+            atomic_helpers: false,
+            debug_scripts: false,
+            package: Package::default(),
+            naming: Naming::default(),
+            dependencies: toml::Table::new(),
+            features: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for Package {
+    fn default() -> Self {
+        Package {
+            version: defaults::CRATE_VERSION.to_string(),
+            edition: defaults::CRATE_EDITION.to_string(),
+            authors: Vec::new(),
+            license: None,
+            description: None,
+            repository: None,
+            rust_version: None,
+            metadata: None,
+        }
+    }
+}
+
+impl Default for Naming {
+    fn default() -> Self {
+        Naming {
+            register_suffix: "Register".to_string(),
+            builder_suffix: "Builder".to_string(),
+            setter_prefix: "with_".to_string(),
+            peripheral_accessor: "{name}0".to_string(),
+            strip_prefixes: false,
+            rename: BTreeMap::new(),
+        }
+    }
+}
+
+impl From<NamingOption> for Naming {
+    fn from(naming: NamingOption) -> Self {
+        let defaults = Naming::default();
+        Naming {
+            register_suffix: naming.register_suffix.unwrap_or(defaults.register_suffix),
+            builder_suffix: naming.builder_suffix.unwrap_or(defaults.builder_suffix),
+            setter_prefix: naming.setter_prefix.unwrap_or(defaults.setter_prefix),
+            peripheral_accessor: naming
+                .peripheral_accessor
+                .unwrap_or(defaults.peripheral_accessor),
+            strip_prefixes: naming.strip_prefixes.unwrap_or(defaults.strip_prefixes),
+            rename: naming.rename.unwrap_or(defaults.rename),
+        }
+    }
+}
+
+impl From<PackageOption> for Package {
+    fn from(package: PackageOption) -> Self {
+        let defaults = Package::default();
+        Package {
+            version: package.version.unwrap_or(defaults.version),
+            edition: package.edition.unwrap_or(defaults.edition),
+            authors: package.authors.unwrap_or(defaults.authors),
+            license: package.license,
+            description: package.description,
+            repository: package.repository,
+            rust_version: package.rust_version,
+            metadata: package.metadata,
+        }
+    }
 }
 
 /// Parses a configuration file that contains the versions of the dependencies.
@@ -24,27 +208,64 @@ pub struct Config {
 /// config_file: &Option<PathBuf> - The path to the configuration file.
 ///
 /// # Returns
-/// A Config struct containing the versions of the dependencies.
-pub fn parse_config(config_file: &Option<PathBuf>) -> Config {
+/// A Result containing a Config struct with the versions of the dependencies.
+pub fn parse_config(config_file: &Option<PathBuf>) -> Result<Config, Error> {
     // If there is a config file, parse it. Otherwise, return the default values.
     if let Some(config_file) = config_file {
-        let content = std::fs::read_to_string(config_file).expect("Failed to read config file");
-        let parsed_config: ConfigOption =
-            toml::from_str(&content).expect("Failed to parse config file");
+        let content =
+            std::fs::read_to_string(config_file).map_err(|e| Error::io(config_file, e))?;
+        let parsed_config: ConfigOption = toml::from_str(&content).map_err(|e| Error::Config {
+            path: config_file.clone(),
+            source: e,
+        })?;
 
         // Check if there are missing versions and use the default values if they are missing
-        Config {
+        let defaults = Config::default();
+        Ok(Config {
             bit_field_version: parsed_config
                 .bit_field_version
-                .unwrap_or_else(|| defaults::BIT_FIELD_VERSION.to_string()),
+                .unwrap_or(defaults.bit_field_version),
             volatile_register_version: parsed_config
                 .volatile_register_version
-                .unwrap_or_else(|| defaults::VOLATILE_REGISTER_VERSION.to_string()),
-        }
+                .unwrap_or(defaults.volatile_register_version),
+            cortex_m_version: parsed_config
+                .cortex_m_version
+                .unwrap_or(defaults.cortex_m_version),
+            critical_section_version: parsed_config
+                .critical_section_version
+                .unwrap_or(defaults.critical_section_version),
+            defmt_version: parsed_config
+                .defmt_version
+                .unwrap_or(defaults.defmt_version),
+            serde_version: parsed_config
+                .serde_version
+                .unwrap_or(defaults.serde_version),
+            libc_version: parsed_config.libc_version.unwrap_or(defaults.libc_version),
+            zero_dependencies: parsed_config
+                .zero_dependencies
+                .unwrap_or(defaults.zero_dependencies),
+            extension_files: parsed_config
+                .extension_files
+                .unwrap_or(defaults.extension_files),
+            svd2rust_api: parsed_config.svd2rust_api.unwrap_or(defaults.svd2rust_api),
+            atomic_helpers: parsed_config
+                .atomic_helpers
+                .unwrap_or(defaults.atomic_helpers),
+            debug_scripts: parsed_config
+                .debug_scripts
+                .unwrap_or(defaults.debug_scripts),
+            package: parsed_config
+                .package
+                .map(Package::from)
+                .unwrap_or(defaults.package),
+            naming: parsed_config
+                .naming
+                .map(Naming::from)
+                .unwrap_or(defaults.naming),
+            dependencies: parsed_config.dependencies.unwrap_or(defaults.dependencies),
+            features: parsed_config.features.unwrap_or(defaults.features),
+        })
     } else {
-        Config {
-            bit_field_version: defaults::BIT_FIELD_VERSION.to_string(),
-            volatile_register_version: defaults::VOLATILE_REGISTER_VERSION.to_string(),
-        }
+        Ok(Config::default())
     }
 }
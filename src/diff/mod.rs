@@ -0,0 +1,813 @@
+//! The diff module compares two versions of a description and classifies every change by
+//! its impact on the API of the generated crate, for the `diff` command.
+//!
+//! Peripherals, clusters, registers, fields, enum variants and interrupts are matched by
+//! name. A change is breaking when code written against the old crate may no longer
+//! compile, such as a removed register, a field that lost its setter or a new variant in
+//! an enum that can be matched exhaustively. Additions are minor changes, and changes
+//! that keep the API but not the behavior, such as a moved register or a new reset value,
+//! are patch changes.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    gen::openocd::bits_label as bits,
+    parse::{utils::Access, Cluster, Enum, Field, Peripheral, PeripheralAccess, Register},
+};
+
+/// The SemverBump enum is the version bump a change requires, from the smallest to the largest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverBump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl SemverBump {
+    /// Applies the bump to a version. For 0.x versions, a major bump increments the minor
+    /// version and a minor bump increments the patch version, following the Cargo
+    /// compatibility rules.
+    ///
+    /// # Arguments
+    /// version: &str - The current version, as major.minor.patch.
+    ///
+    /// # Returns
+    /// The bumped version, or None if the version is not a valid major.minor.patch version.
+    pub fn apply(self, version: &str) -> Option<String> {
+        let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+        let (major, minor, patch) = (parts.next()??, parts.next()??, parts.next()??);
+
+        let bump = match (self, major) {
+            (SemverBump::Major, 0) => SemverBump::Minor,
+            (SemverBump::Minor, 0) => SemverBump::Patch,
+            (bump, _) => bump,
+        };
+        Some(match bump {
+            SemverBump::None => format!("{}.{}.{}", major, minor, patch),
+            SemverBump::Patch => format!("{}.{}.{}", major, minor, patch + 1),
+            SemverBump::Minor => format!("{}.{}.0", major, minor + 1),
+            SemverBump::Major => format!("{}.0.0", major + 1),
+        })
+    }
+}
+
+impl fmt::Display for SemverBump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SemverBump::None => "none",
+            SemverBump::Patch => "patch",
+            SemverBump::Minor => "minor",
+            SemverBump::Major => "major",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The Change struct is a single difference between the two descriptions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// The path of the changed item, e.g. "UART.MODE.char_len"
+    pub path: String,
+    /// What changed, e.g. "bit range changed from [2:1] to [3:1]"
+    pub message: String,
+    /// The version bump the change requires
+    pub bump: SemverBump,
+}
+
+impl Change {
+    /// Returns whether the change breaks code written against the old generated crate.
+    pub fn is_breaking(&self) -> bool {
+        self.bump == SemverBump::Major
+    }
+}
+
+/// The DiffReport struct is the list of changes between two descriptions.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffReport {
+    pub changes: Vec<Change>,
+}
+
+impl DiffReport {
+    /// Returns the version bump the generated crate needs, the largest of its changes.
+    pub fn suggested_bump(&self) -> SemverBump {
+        self.changes
+            .iter()
+            .map(|change| change.bump)
+            .max()
+            .unwrap_or(SemverBump::None)
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let breaking = self.changes.iter().filter(|c| c.is_breaking()).count();
+        writeln!(
+            f,
+            "{} changes, {} breaking. Suggested version bump: {}",
+            self.changes.len(),
+            breaking,
+            self.suggested_bump()
+        )?;
+
+        for (title, is_breaking) in [("Breaking", true), ("Non-breaking", false)] {
+            let changes = self
+                .changes
+                .iter()
+                .filter(|change| change.is_breaking() == is_breaking)
+                .collect::<Vec<_>>();
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "\n{}:", title)?;
+            for change in changes {
+                write!(f, "  {}: {}", change.path, change.message)?;
+                if !is_breaking {
+                    write!(f, " ({})", change.bump)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares two descriptions.
+///
+/// # Arguments
+/// old: &PeripheralAccess - The previous version of the description.
+/// new: &PeripheralAccess - The new version of the description.
+///
+/// # Returns
+/// The DiffReport, with the changes in the order of the descriptions.
+pub fn diff(old: &PeripheralAccess, new: &PeripheralAccess) -> DiffReport {
+    let mut differ = Differ::default();
+
+    for_each_pair(
+        &old.peripherals,
+        &new.peripherals,
+        |peripheral| &peripheral.name,
+        |old, new| match (old, new) {
+            (Some(old), Some(new)) => differ.peripheral(old, new),
+            (Some(old), None) => differ.push(&old.name, "peripheral removed", SemverBump::Major),
+            (None, Some(new)) => differ.push(&new.name, "peripheral added", SemverBump::Minor),
+            (None, None) => {}
+        },
+    );
+
+    DiffReport {
+        changes: differ.changes,
+    }
+}
+
+/// Calls `f` with the items of two lists that have the same name, then with the items
+/// that are only in one of them: the removed ones in the old order, then the added ones.
+fn for_each_pair<'a, T>(
+    old: &'a [T],
+    new: &'a [T],
+    name: impl Fn(&T) -> &str,
+    mut f: impl FnMut(Option<&'a T>, Option<&'a T>),
+) {
+    for old_item in old {
+        let new_item = new.iter().find(|new_item| name(new_item) == name(old_item));
+        f(Some(old_item), new_item);
+    }
+    for new_item in new {
+        if !old.iter().any(|old_item| name(old_item) == name(new_item)) {
+            f(None, Some(new_item));
+        }
+    }
+}
+
+/// Whether the generated API can read and write a register or field with an access type.
+fn capabilities(access: &Access) -> (bool, bool) {
+    match access {
+        Access::ReadOnly => (true, false),
+        Access::WriteOnly => (false, true),
+        Access::ReadWrite | Access::WriteToClear => (true, true),
+    }
+}
+
+/// The bump of an access change: losing the getter or setter breaks the API, gaining one
+/// extends it, and anything else (such as read-write to write-to-clear) changes behavior.
+fn access_bump(old: &Access, new: &Access) -> SemverBump {
+    let (old_read, old_write) = capabilities(old);
+    let (new_read, new_write) = capabilities(new);
+    if (old_read && !new_read) || (old_write && !new_write) {
+        SemverBump::Major
+    } else if new_read != old_read || new_write != old_write {
+        SemverBump::Minor
+    } else {
+        SemverBump::Patch
+    }
+}
+
+/// The Differ struct collects the changes while walking the two descriptions.
+#[derive(Default)]
+struct Differ {
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn push(&mut self, path: &str, message: impl Into<String>, bump: SemverBump) {
+        self.changes.push(Change {
+            path: path.to_string(),
+            message: message.into(),
+            bump,
+        });
+    }
+
+    fn peripheral(&mut self, old: &Peripheral, new: &Peripheral) {
+        let path = &new.name;
+        if old.base_address != new.base_address {
+            self.push(
+                path,
+                format!(
+                    "base address changed from 0x{:08X} to 0x{:08X}",
+                    old.base_address, new.base_address
+                ),
+                SemverBump::Patch,
+            );
+        }
+
+        let old_interrupts = old.interrupts.as_deref().unwrap_or_default();
+        let new_interrupts = new.interrupts.as_deref().unwrap_or_default();
+        for_each_pair(
+            old_interrupts,
+            new_interrupts,
+            |interrupt| &interrupt.name,
+            |old, new| match (old, new) {
+                (Some(old), Some(new)) if old.number != new.number => self.push(
+                    &format!("{}.{}", path, new.name),
+                    format!(
+                        "interrupt number changed from {} to {}",
+                        old.number, new.number
+                    ),
+                    SemverBump::Patch,
+                ),
+                (Some(old), None) => self.push(
+                    &format!("{}.{}", path, old.name),
+                    "interrupt removed",
+                    SemverBump::Major,
+                ),
+                (None, Some(new)) => self.push(
+                    &format!("{}.{}", path, new.name),
+                    "interrupt added",
+                    SemverBump::Minor,
+                ),
+                _ => {}
+            },
+        );
+
+        self.block(
+            path,
+            &old.registers,
+            old.clusters(),
+            &new.registers,
+            new.clusters(),
+        );
+    }
+
+    /// Compares the registers and clusters of two register blocks.
+    fn block(
+        &mut self,
+        path: &str,
+        old_registers: &[Register],
+        old_clusters: &[Cluster],
+        new_registers: &[Register],
+        new_clusters: &[Cluster],
+    ) {
+        for_each_pair(
+            old_registers,
+            new_registers,
+            |register| &register.name,
+            |old, new| match (old, new) {
+                (Some(old), Some(new)) => {
+                    self.register(&format!("{}.{}", path, new.name), old, new)
+                }
+                (Some(old), None) => self.push(
+                    &format!("{}.{}", path, old.name),
+                    "register removed",
+                    SemverBump::Major,
+                ),
+                (None, Some(new)) => self.push(
+                    &format!("{}.{}", path, new.name),
+                    format!("register added at offset 0x{:X}", new.offset),
+                    SemverBump::Minor,
+                ),
+                (None, None) => {}
+            },
+        );
+
+        for_each_pair(
+            old_clusters,
+            new_clusters,
+            |cluster| &cluster.name,
+            |old, new| match (old, new) {
+                (Some(old), Some(new)) => self.cluster(&format!("{}.{}", path, new.name), old, new),
+                (Some(old), None) => self.push(
+                    &format!("{}.{}", path, old.name),
+                    "cluster removed",
+                    SemverBump::Major,
+                ),
+                (None, Some(new)) => self.push(
+                    &format!("{}.{}", path, new.name),
+                    format!("cluster added at offset 0x{:X}", new.offset),
+                    SemverBump::Minor,
+                ),
+                (None, None) => {}
+            },
+        );
+    }
+
+    fn cluster(&mut self, path: &str, old: &Cluster, new: &Cluster) {
+        if old.offset != new.offset {
+            self.push(
+                path,
+                format!(
+                    "cluster moved from offset 0x{:X} to 0x{:X}",
+                    old.offset, new.offset
+                ),
+                SemverBump::Patch,
+            );
+        }
+        if old.count != new.count {
+            // The cluster changes between a struct and an array, or the array length changes
+            let count = |count: Option<usize>| count.map_or("none".to_string(), |c| c.to_string());
+            self.push(
+                path,
+                format!(
+                    "element count changed from {} to {}",
+                    count(old.count),
+                    count(new.count)
+                ),
+                SemverBump::Major,
+            );
+        } else if old.count.is_some() && old.element_size() != new.element_size() {
+            self.push(
+                path,
+                format!(
+                    "stride changed from 0x{:X} to 0x{:X}",
+                    old.element_size(),
+                    new.element_size()
+                ),
+                SemverBump::Patch,
+            );
+        }
+
+        self.block(
+            path,
+            &old.registers,
+            old.clusters(),
+            &new.registers,
+            new.clusters(),
+        );
+    }
+
+    fn register(&mut self, path: &str, old: &Register, new: &Register) {
+        if old.offset != new.offset {
+            self.push(
+                path,
+                format!(
+                    "register moved from offset 0x{:X} to 0x{:X}",
+                    old.offset, new.offset
+                ),
+                SemverBump::Patch,
+            );
+        }
+        if old.size != new.size {
+            self.push(
+                path,
+                format!("size changed from {} to {} bits", old.size, new.size),
+                SemverBump::Major,
+            );
+        }
+        if old.access != new.access {
+            self.push(
+                path,
+                format!(
+                    "access changed from {} to {}",
+                    old.access.as_str(),
+                    new.access.as_str()
+                ),
+                access_bump(&old.access, &new.access),
+            );
+        }
+        if old.reset_value() != new.reset_value() {
+            self.push(
+                path,
+                format!(
+                    "reset value changed from 0x{:X} to 0x{:X}",
+                    old.reset_value(),
+                    new.reset_value()
+                ),
+                SemverBump::Patch,
+            );
+        } else if old.reset_value != new.reset_value {
+            // The declared value replaces the one built from the field defaults, or the reverse
+            let value =
+                |reset: Option<usize>| reset.map_or("none".to_string(), |r| format!("0x{:X}", r));
+            self.push(
+                path,
+                format!(
+                    "declared reset value changed from {} to {}",
+                    value(old.reset_value),
+                    value(new.reset_value)
+                ),
+                SemverBump::Patch,
+            );
+        }
+        if old.description != new.description {
+            self.push(path, "description changed", SemverBump::Patch);
+        }
+
+        match (&old.fields, &new.fields) {
+            (Some(old_fields), Some(new_fields)) => {
+                for_each_pair(
+                    old_fields,
+                    new_fields,
+                    |field| &field.name,
+                    |old_field, new_field| match (old_field, new_field) {
+                        (Some(old_field), Some(new_field)) => self.field(
+                            &format!("{}.{}", path, new_field.name),
+                            (old, old_field),
+                            (new, new_field),
+                        ),
+                        (Some(old_field), None) => self.push(
+                            &format!("{}.{}", path, old_field.name),
+                            "field removed",
+                            SemverBump::Major,
+                        ),
+                        (None, Some(new_field)) => self.push(
+                            &format!("{}.{}", path, new_field.name),
+                            format!("field added at {}", bits(new_field)),
+                            SemverBump::Minor,
+                        ),
+                        (None, None) => {}
+                    },
+                );
+            }
+            // Registers without fields are plain values instead of a register module
+            (Some(_), None) => self.push(path, "fields removed", SemverBump::Major),
+            (None, Some(_)) => self.push(path, "fields added", SemverBump::Major),
+            (None, None) => {}
+        }
+    }
+
+    fn field(
+        &mut self,
+        path: &str,
+        (old_register, old): (&Register, &Field),
+        (new_register, new): (&Register, &Field),
+    ) {
+        let (old_start, old_end) = old.bit_range.get_bit_range();
+        let (new_start, new_end) = new.bit_range.get_bit_range();
+        if (old_start, old_end) != (new_start, new_end) {
+            // A field that keeps its width only moves, while a new width changes the
+            // values the field can hold, and maybe the type of its getter and setter
+            let bump = if old_end - old_start == new_end - new_start {
+                SemverBump::Patch
+            } else {
+                SemverBump::Major
+            };
+            self.push(
+                path,
+                format!("bit range changed from {} to {}", bits(old), bits(new)),
+                bump,
+            );
+        }
+
+        // Fields without their own access follow the register, whose change is already reported
+        let old_access = old.access.as_ref().unwrap_or(&old_register.access);
+        let new_access = new.access.as_ref().unwrap_or(&new_register.access);
+        let inherited = old.access.is_none() && new.access.is_none();
+        if old_access != new_access && !inherited {
+            self.push(
+                path,
+                format!(
+                    "access changed from {} to {}",
+                    old_access.as_str(),
+                    new_access.as_str()
+                ),
+                access_bump(old_access, new_access),
+            );
+        }
+
+        if old.default_bits() != new.default_bits() {
+            let value =
+                |bits: Option<u64>| bits.map_or("none".to_string(), |b| format!("0x{:X}", b));
+            self.push(
+                path,
+                format!(
+                    "reset value changed from {} to {}",
+                    value(old.default_bits()),
+                    value(new.default_bits())
+                ),
+                SemverBump::Patch,
+            );
+        }
+
+        match (&old.enums, &new.enums) {
+            (Some(old_enum), Some(new_enum)) => self.field_enum(path, old_enum, new_enum),
+            (Some(old_enum), None) => self.push(
+                path,
+                format!("enum {} removed, the field is now a number", old_enum.name),
+                SemverBump::Major,
+            ),
+            (None, Some(new_enum)) => self.push(
+                path,
+                format!(
+                    "enum {} added, the field is no longer a number",
+                    new_enum.name
+                ),
+                SemverBump::Major,
+            ),
+            (None, None) => {}
+        }
+    }
+
+    fn field_enum(&mut self, path: &str, old: &Enum, new: &Enum) {
+        if old.name != new.name {
+            self.push(
+                path,
+                format!("enum renamed from {} to {}", old.name, new.name),
+                SemverBump::Major,
+            );
+        }
+
+        for_each_pair(
+            &old.values,
+            &new.values,
+            |value| &value.name,
+            |old_value, new_value| match (old_value, new_value) {
+                (Some(old_value), Some(new_value)) if old_value.value != new_value.value => self
+                    .push(
+                        &format!("{}.{}", path, new_value.name),
+                        format!(
+                            "variant value changed from 0x{:X} to 0x{:X}",
+                            old_value.value, new_value.value
+                        ),
+                        SemverBump::Patch,
+                    ),
+                (Some(old_value), None) => self.push(
+                    &format!("{}.{}", path, old_value.name),
+                    "variant removed",
+                    SemverBump::Major,
+                ),
+                // The generated enums are exhaustive, so matches on them stop compiling
+                (None, Some(new_value)) => self.push(
+                    &format!("{}.{}", path, new_value.name),
+                    format!("variant added with value 0x{:X}", new_value.value),
+                    SemverBump::Major,
+                ),
+                _ => {}
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// A description with a UART peripheral, with an interrupt and a MODE register with a
+    /// single-bit field and an enum field.
+    fn uart() -> Value {
+        json!({
+            "name": "Test",
+            "register_size": 32,
+            "peripherals": [{
+                "name": "UART",
+                "base_address": "0xE0000000",
+                "interrupts": [{ "name": "UART0", "number": 59, "description": "UART 0" }],
+                "registers": [{
+                    "name": "MODE",
+                    "access": "read-write",
+                    "description": "Mode",
+                    "offset": "0x4",
+                    "size": 32,
+                    "fields": [
+                        { "name": "clk_sel", "bit_range": "0", "description": "Clock" },
+                        {
+                            "name": "char_len",
+                            "bit_range": "[2:1]",
+                            "description": "Character length",
+                            "enums": {
+                                "name": "CharacterLength",
+                                "values": [
+                                    { "name": "EightBits", "value": "0", "description": "8 bits" },
+                                    { "name": "SevenBits", "value": "2", "description": "7 bits" }
+                                ]
+                            }
+                        }
+                    ]
+                }]
+            }]
+        })
+    }
+
+    /// Compares the UART description with a copy changed by `change`.
+    fn diff_with(change: impl FnOnce(&mut Value)) -> DiffReport {
+        let mut new = uart();
+        change(&mut new);
+        let old = serde_json::from_value(uart()).unwrap();
+        let new = serde_json::from_value(new).unwrap();
+        diff(&old, &new)
+    }
+
+    /// Returns the only change of a report, with its path and bump.
+    fn single_change(report: &DiffReport) -> (&str, SemverBump) {
+        assert_eq!(report.changes.len(), 1, "{:?}", report.changes);
+        let change = &report.changes[0];
+        (&change.path, change.bump)
+    }
+
+    fn register(description: &mut Value) -> &mut Value {
+        &mut description["peripherals"][0]["registers"][0]
+    }
+
+    fn values(description: &mut Value) -> &mut Value {
+        &mut register(description)["fields"][1]["enums"]["values"]
+    }
+
+    #[test]
+    fn identical_descriptions_need_no_bump() {
+        let report = diff_with(|_| {});
+        assert!(report.changes.is_empty());
+        assert_eq!(report.suggested_bump(), SemverBump::None);
+    }
+
+    #[test]
+    fn removals_are_major() {
+        let report = diff_with(|d| d["peripherals"][0]["registers"] = json!([]));
+        assert_eq!(single_change(&report), ("UART.MODE", SemverBump::Major));
+
+        let report = diff_with(|d| {
+            register(d)["fields"].as_array_mut().unwrap().remove(0);
+        });
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.clk_sel", SemverBump::Major)
+        );
+
+        let report = diff_with(|d| d["peripherals"][0]["interrupts"] = json!([]));
+        assert_eq!(single_change(&report), ("UART.UART0", SemverBump::Major));
+    }
+
+    #[test]
+    fn additions_are_minor() {
+        let report = diff_with(|d| {
+            let mut status = register(d).clone();
+            status["name"] = json!("STATUS");
+            status["offset"] = json!("0x8");
+            d["peripherals"][0]["registers"]
+                .as_array_mut()
+                .unwrap()
+                .push(status);
+        });
+        assert_eq!(single_change(&report), ("UART.STATUS", SemverBump::Minor));
+
+        let report = diff_with(|d| {
+            register(d)["fields"]
+                .as_array_mut()
+                .unwrap()
+                .push(json!({ "name": "parity", "bit_range": "[5:3]", "description": "Parity" }));
+        });
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.parity", SemverBump::Minor)
+        );
+    }
+
+    #[test]
+    fn behavior_changes_are_patches() {
+        let report = diff_with(|d| register(d)["offset"] = json!("0x8"));
+        assert_eq!(single_change(&report), ("UART.MODE", SemverBump::Patch));
+
+        let report = diff_with(|d| d["peripherals"][0]["interrupts"][0]["number"] = json!(60));
+        assert_eq!(single_change(&report), ("UART.UART0", SemverBump::Patch));
+
+        let report = diff_with(|d| register(d)["fields"][0]["default_value"] = json!(1));
+        assert_eq!(report.suggested_bump(), SemverBump::Patch);
+    }
+
+    #[test]
+    fn declared_reset_value_changes_are_patches() {
+        let report = diff_with(|d| register(d)["reset_value"] = json!("0x28B"));
+        assert_eq!(single_change(&report), ("UART.MODE", SemverBump::Patch));
+        assert_eq!(
+            report.changes[0].message,
+            "reset value changed from 0x0 to 0x28B"
+        );
+
+        // Declaring the value the field defaults already give changes nothing but the description
+        let report = diff_with(|d| register(d)["reset_value"] = json!("0x0"));
+        assert_eq!(single_change(&report), ("UART.MODE", SemverBump::Patch));
+        assert_eq!(
+            report.changes[0].message,
+            "declared reset value changed from none to 0x0"
+        );
+    }
+
+    #[test]
+    fn access_changes_depend_on_the_lost_capabilities() {
+        let report = diff_with(|d| register(d)["access"] = json!("read-only"));
+        assert_eq!(single_change(&report), ("UART.MODE", SemverBump::Major));
+
+        let report = diff_with(|d| register(d)["fields"][0]["access"] = json!("read-only"));
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.clk_sel", SemverBump::Major)
+        );
+
+        assert_eq!(
+            access_bump(&Access::ReadOnly, &Access::ReadWrite),
+            SemverBump::Minor
+        );
+        assert_eq!(
+            access_bump(&Access::ReadWrite, &Access::WriteToClear),
+            SemverBump::Patch
+        );
+    }
+
+    #[test]
+    fn bit_range_changes_are_major_only_when_the_width_changes() {
+        let report = diff_with(|d| register(d)["fields"][1]["bit_range"] = json!("[3:2]"));
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.char_len", SemverBump::Patch)
+        );
+
+        let report = diff_with(|d| register(d)["fields"][1]["bit_range"] = json!("[3:1]"));
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.char_len", SemverBump::Major)
+        );
+    }
+
+    #[test]
+    fn enum_variant_changes() {
+        let report = diff_with(|d| {
+            values(d)
+                .as_array_mut()
+                .unwrap()
+                .push(json!({ "name": "SixBits", "value": "3", "description": "6 bits" }));
+        });
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.char_len.SixBits", SemverBump::Major)
+        );
+
+        let report = diff_with(|d| {
+            values(d).as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.char_len.SevenBits", SemverBump::Major)
+        );
+
+        let report = diff_with(|d| values(d)[1]["value"] = json!("1"));
+        assert_eq!(
+            single_change(&report),
+            ("UART.MODE.char_len.SevenBits", SemverBump::Patch)
+        );
+    }
+
+    #[test]
+    fn suggested_bump_is_the_largest_change() {
+        let report = diff_with(|d| {
+            register(d)["offset"] = json!("0x8");
+            register(d)["access"] = json!("write-only");
+        });
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(report.suggested_bump(), SemverBump::Major);
+    }
+
+    #[test]
+    fn apply_bumps_stable_versions() {
+        assert_eq!(SemverBump::None.apply("1.2.3").unwrap(), "1.2.3");
+        assert_eq!(SemverBump::Patch.apply("1.2.3").unwrap(), "1.2.4");
+        assert_eq!(SemverBump::Minor.apply("1.2.3").unwrap(), "1.3.0");
+        assert_eq!(SemverBump::Major.apply("1.2.3").unwrap(), "2.0.0");
+    }
+
+    #[test]
+    fn apply_follows_the_cargo_rules_for_0x_versions() {
+        assert_eq!(SemverBump::None.apply("0.4.2").unwrap(), "0.4.2");
+        assert_eq!(SemverBump::Patch.apply("0.4.2").unwrap(), "0.4.3");
+        assert_eq!(SemverBump::Minor.apply("0.4.2").unwrap(), "0.4.3");
+        assert_eq!(SemverBump::Major.apply("0.4.2").unwrap(), "0.5.0");
+    }
+
+    #[test]
+    fn apply_rejects_invalid_versions() {
+        assert_eq!(SemverBump::Patch.apply("1.2"), None);
+        assert_eq!(SemverBump::Patch.apply("1.x.3"), None);
+        assert_eq!(SemverBump::Patch.apply(""), None);
+    }
+}
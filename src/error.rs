@@ -0,0 +1,109 @@
+//! The error module contains the error type returned by the library API.
+
+use std::{fmt, path::PathBuf};
+
+/// The Error enum represents everything that can go wrong while loading a description
+/// and generating a peripheral access crate from it.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A JSON description file could not be parsed.
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The TOML configuration file could not be parsed.
+    Config {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    /// The output was requested in `OUT_DIR` but the variable is not set.
+    MissingOutDir,
+    /// Generated files were edited by hand and would be overwritten or deleted.
+    ModifiedFiles(Vec<PathBuf>),
+    /// No register matches the given `PERIPHERAL.REGISTER` path.
+    UnknownRegister(String),
+    /// The register has no field with the given name.
+    UnknownField { register: String, field: String },
+    /// The value is not a number, or not a variant of the field, or does not fit in the field.
+    InvalidValue { field: String, value: String },
+    /// A memory dump could not be decoded.
+    InvalidDump { path: PathBuf, reason: String },
+    /// Different names of the description would be generated as the same identifier.
+    NameCollisions(Vec<String>),
+}
+
+impl Error {
+    /// Creates an Error::Io from an io::Error and the path it relates to.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Json { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Config { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::MissingOutDir => {
+                write!(f, "OUT_DIR is not set, is this running in a build script?")
+            }
+            Error::ModifiedFiles(files) => {
+                write!(
+                    f,
+                    "refusing to overwrite files that were edited by hand (use --force to overwrite them):"
+                )?;
+                for file in files {
+                    write!(f, "\n  {}", file.display())?;
+                }
+                Ok(())
+            }
+            Error::UnknownRegister(path) => write!(f, "unknown register: {}", path),
+            Error::UnknownField { register, field } => {
+                write!(f, "{} has no field named {}", register, field)
+            }
+            Error::InvalidValue { field, value } => {
+                write!(f, "invalid value for {}: {}", field, value)
+            }
+            Error::InvalidDump { path, reason } => write!(f, "{}: {}", path.display(), reason),
+            Error::NameCollisions(collisions) => {
+                write!(
+                    f,
+                    "names collide after their conversion to Rust identifiers:"
+                )?;
+                for collision in collisions {
+                    write!(f, "\n  {}", collision)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Json { source, .. } => Some(source),
+            Error::Config { source, .. } => Some(source),
+            Error::MissingOutDir
+            | Error::ModifiedFiles(_)
+            | Error::UnknownRegister(_)
+            | Error::UnknownField { .. }
+            | Error::InvalidValue { .. }
+            | Error::InvalidDump { .. }
+            | Error::NameCollisions(_) => None,
+        }
+    }
+}
+
+/// A Result type alias using the library Error type.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,399 @@
+//! The gen::atomic module generates atomic set/clear/toggle helpers for the read-write
+//! registers, so an interrupt handler touching the same register cannot lose an update
+//! the way it can with the read-modify-write of `RegisterRW::modify`.
+//!
+//! The helpers use the best mechanism the description declares:
+//! - the hardware alias registers of the peripheral, where writing a mask sets, clears
+//!   or toggles those bits,
+//! - the Cortex-M bit-band alias region, for setting and clearing single bits,
+//! - a critical section otherwise, behind the `critical-section` feature of the generated crate.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use super::naming;
+use super::utils::{bit_range_mask, get_smallest_rust_type};
+use crate::{
+    config::{Config, Naming},
+    parse::{
+        utils::Access, AtomicAliases, BitBandRegion, Cluster, Field, Peripheral, PeripheralAccess,
+        Register,
+    },
+};
+
+/// The AtomicAccess struct describes the atomic access mechanisms available to the
+/// registers of a register block, i.e. of a peripheral or of one of its clusters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AtomicAccess<'a> {
+    /// Whether atomic helpers are generated at all
+    enabled: bool,
+    /// The alias registers of the peripheral
+    aliases: Option<&'a AtomicAliases>,
+    /// The bit-band regions of the description
+    bit_band_regions: &'a [BitBandRegion],
+    /// The address of the register block, or of its first element in cluster arrays
+    block_address: usize,
+    /// The distance in bytes between the first and the last element of the register
+    /// block in cluster arrays, and 0 otherwise
+    block_extent: usize,
+    /// Whether the critical-section crate can be used as a fallback
+    critical_section: bool,
+}
+
+impl<'a> AtomicAccess<'a> {
+    /// Finds the atomic access mechanisms available to a peripheral.
+    ///
+    /// # Arguments
+    /// pac: &PeripheralAccess - The parsed peripheral access data structure.
+    /// peripheral: &Peripheral - The peripheral the registers belong to.
+    /// config: &Config - The configuration file that contains the generation options.
+    ///
+    /// # Returns
+    /// The AtomicAccess of the peripheral.
+    pub fn new(pac: &'a PeripheralAccess, peripheral: &'a Peripheral, config: &Config) -> Self {
+        AtomicAccess {
+            enabled: config.atomic_helpers,
+            aliases: peripheral.atomic_aliases.as_ref(),
+            bit_band_regions: pac.bit_band_regions.as_deref().unwrap_or_default(),
+            block_address: peripheral.base_address,
+            block_extent: 0,
+            critical_section: !config.zero_dependencies,
+        }
+    }
+
+    /// Returns the atomic access mechanisms available to the registers of a cluster of
+    /// the register block.
+    ///
+    /// # Arguments
+    /// cluster: &Cluster - The cluster of the register block.
+    ///
+    /// # Returns
+    /// The AtomicAccess of the cluster.
+    pub fn in_cluster(&self, cluster: &Cluster) -> Self {
+        let elements = cluster.count.unwrap_or(1);
+        AtomicAccess {
+            block_address: self.block_address + cluster.offset,
+            block_extent: self.block_extent + cluster.element_size() * (elements - 1),
+            ..*self
+        }
+    }
+
+    /// Finds the bit-band region that contains every address of a register, in every
+    /// element of the cluster arrays it is in.
+    ///
+    /// # Arguments
+    /// register: &Register - The register of the register block.
+    ///
+    /// # Returns
+    /// The bit-band region of the register, if there is one.
+    fn bit_band(&self, register: &Register) -> Option<&'a BitBandRegion> {
+        let first = self.block_address + register.offset;
+        let last = first + self.block_extent + register.size / 8 - 1;
+        self.bit_band_regions
+            .iter()
+            .find(|region| region.contains(first) && region.contains(last))
+    }
+}
+
+/// An atomic operation on the bits of a register.
+#[derive(Clone, Copy)]
+enum Operation {
+    Set,
+    Clear,
+    Toggle,
+}
+
+impl Operation {
+    /// The name of the operation, as used in the method names.
+    fn name(self) -> &'static str {
+        match self {
+            Operation::Set => "set",
+            Operation::Clear => "clear",
+            Operation::Toggle => "toggle",
+        }
+    }
+
+    /// The offset of the alias register for the operation, if the peripheral has one.
+    fn alias_offset(self, aliases: &AtomicAliases) -> Option<usize> {
+        match self {
+            Operation::Set => aliases.set,
+            Operation::Clear => aliases.clear,
+            Operation::Toggle => aliases.toggle,
+        }
+    }
+
+    /// The expression that applies the operation to `value`, for the critical section fallback.
+    fn apply(self) -> TokenStream {
+        match self {
+            Operation::Set => quote! { value | mask },
+            Operation::Clear => quote! { value & !mask },
+            Operation::Toggle => quote! { value ^ mask },
+        }
+    }
+}
+
+const OPERATIONS: [Operation; 3] = [Operation::Set, Operation::Clear, Operation::Toggle];
+
+/// Whether a field gets the single-bit helpers: it is a writable single bit without enum.
+fn has_bit_helpers(field: &Field) -> bool {
+    let field_access = field.access.as_ref().unwrap_or(&Access::ReadWrite);
+    field.bit_range.is_single_bit()
+        && field.enums.is_none()
+        && matches!(field_access, Access::ReadWrite | Access::WriteOnly)
+}
+
+/// Generates the atomic helpers of a read-write register: `<op>_bits_atomic(mask)` for
+/// the whole register, `<op>_<field>_atomic()` for its writable single-bit fields and,
+/// with the critical-section fallback, `modify_atomic(f)`.
+///
+/// # Arguments
+/// register: &Register - The register to generate the helpers for. It must have fields.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the register block.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing an impl block on the register struct, or nothing if the
+/// helpers are disabled, the register is not read-write or no helper is available.
+pub fn gen_register_atomics(
+    register: &Register,
+    atomic: &AtomicAccess,
+    naming: &Naming,
+) -> TokenStream {
+    if !atomic.enabled || !matches!(register.access, Access::ReadWrite) {
+        return quote! {};
+    }
+
+    let register_struct_name = naming::register_type(&register.name, naming);
+    let register_builder_name = naming::builder_type(&register.name, naming);
+    let register_type = get_smallest_rust_type(register.size);
+    let fields = register.fields.as_ref().unwrap();
+    let cs_attr = quote! { #[cfg(feature = "critical-section")] };
+    let bit_band = atomic.bit_band(register);
+
+    let mut methods = Vec::new();
+
+    // The mask operations on the whole register
+    for operation in OPERATIONS {
+        let method_name = format_ident!("{}_bits_atomic", operation.name());
+        let doc = format!(
+            "Atomically {}s the bits of the mask in the register",
+            operation.name()
+        );
+
+        let alias_offset = atomic
+            .aliases
+            .and_then(|aliases| operation.alias_offset(aliases));
+        let (attr, body) = if let Some(alias_offset) = alias_offset {
+            (
+                quote! {},
+                quote! {
+                    let alias = (self as *const Self as usize + #alias_offset) as *mut #register_type;
+                    unsafe { core::ptr::write_volatile(alias, mask) }
+                },
+            )
+        } else if atomic.critical_section {
+            let apply = operation.apply();
+            (
+                cs_attr.clone(),
+                quote! {
+                    critical_section::with(|_| unsafe { self.inner.modify(|value| #apply) })
+                },
+            )
+        } else {
+            continue;
+        };
+
+        methods.push(quote! {
+            #[doc = #doc]
+            #attr
+            #[inline(always)]
+            pub fn #method_name(&self, mask: #register_type) {
+                #body
+            }
+        });
+    }
+
+    // The operations on the writable single-bit fields
+    for field in fields.iter().filter(|field| has_bit_helpers(field)) {
+        let (bit, _) = field.bit_range.get_bit_range();
+        let mask = bit_range_mask(bit, bit);
+
+        for operation in OPERATIONS {
+            let method_name = naming::atomic_bit_helper(operation.name(), &field.name);
+            let doc = format!("Atomically {}s the {} bit", operation.name(), field.name);
+            let mask_method_name = format_ident!("{}_bits_atomic", operation.name());
+
+            let has_alias = atomic
+                .aliases
+                .and_then(|aliases| operation.alias_offset(aliases))
+                .is_some();
+            let bit_band_value = match operation {
+                Operation::Set => Some(quote! { 1 }),
+                Operation::Clear => Some(quote! { 0 }),
+                Operation::Toggle => None,
+            };
+
+            // Alias registers set the whole mask at once, so they are preferred over bit-banding
+            let (attr, body) = match (bit_band, bit_band_value) {
+                (Some(region), Some(value)) if !has_alias => {
+                    let origin = region.origin;
+                    let alias_base = region.alias;
+                    (
+                        quote! {},
+                        quote! {
+                            let offset = self as *const Self as usize - #origin;
+                            let alias = (#alias_base + offset * 32 + #bit * 4) as *mut u32;
+                            unsafe { core::ptr::write_volatile(alias, #value) }
+                        },
+                    )
+                }
+                _ if has_alias => (quote! {}, quote! { self.#mask_method_name(#mask) }),
+                _ if atomic.critical_section => {
+                    (cs_attr.clone(), quote! { self.#mask_method_name(#mask) })
+                }
+                _ => continue,
+            };
+
+            methods.push(quote! {
+                #[doc = #doc]
+                #attr
+                #[inline(always)]
+                pub fn #method_name(&self) {
+                    #body
+                }
+            });
+        }
+    }
+
+    if atomic.critical_section {
+        methods.push(quote! {
+            /// Reads, modifies and writes the register inside a critical section
+            #cs_attr
+            #[inline(always)]
+            pub fn modify_atomic<F>(&self, f: F)
+            where
+                F: FnOnce(#register_builder_name) -> #register_builder_name,
+            {
+                critical_section::with(|_| unsafe {
+                    self.inner.modify(|value| f(#register_builder_name::from_bits(value)).into_bits())
+                })
+            }
+        });
+    }
+
+    if methods.is_empty() {
+        return quote! {};
+    }
+
+    quote! {
+        impl #register_struct_name {
+            #(#methods)*
+        }
+    }
+}
+
+/// Finds the atomic helpers a read-write register cannot get: all of them if it has no
+/// fields, since it is not generated, and those no mechanism is available for otherwise.
+///
+/// # Arguments
+/// register: &Register - The read-write register.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the register block.
+///
+/// # Returns
+/// A description of the missing helpers, or None if the register gets all of them.
+fn find_missing_helpers(register: &Register, atomic: &AtomicAccess) -> Option<String> {
+    let Some(fields) = &register.fields else {
+        return Some("no atomic helpers, the register has no fields".to_string());
+    };
+    if atomic.critical_section {
+        return None;
+    }
+
+    let bit_band = atomic.bit_band(register).is_some();
+    let bit_helpers = fields.iter().any(has_bit_helpers);
+    let mut missing = Vec::new();
+    for operation in OPERATIONS {
+        if atomic
+            .aliases
+            .and_then(|aliases| operation.alias_offset(aliases))
+            .is_some()
+        {
+            continue;
+        }
+        missing.push(format!("{}_bits_atomic", operation.name()));
+        // Bit-banding still sets and clears the single bits
+        if bit_helpers && (!bit_band || matches!(operation, Operation::Toggle)) {
+            missing.push(format!("{}_<field>_atomic", operation.name()));
+        }
+    }
+
+    let last = missing.pop()?;
+    let missing = if missing.is_empty() {
+        last
+    } else {
+        format!("{} or {}", missing.join(", "), last)
+    };
+    Some(format!(
+        "no {} helpers, the peripheral has no alias register for them and \
+         zero_dependencies leaves out the critical-section fallback",
+        missing
+    ))
+}
+
+/// Finds the missing atomic helpers of the read-write registers of a register block and
+/// everything in it.
+fn check_block(
+    path: &str,
+    registers: &[Register],
+    clusters: &[Cluster],
+    atomic: &AtomicAccess,
+    warnings: &mut Vec<String>,
+) {
+    for register in registers {
+        if !matches!(register.access, Access::ReadWrite) {
+            continue;
+        }
+        if let Some(missing) = find_missing_helpers(register, atomic) {
+            warnings.push(format!("{}.{}: {}", path, register.name, missing));
+        }
+    }
+
+    for cluster in clusters {
+        check_block(
+            &format!("{}.{}", path, cluster.name),
+            &cluster.registers,
+            cluster.clusters(),
+            &atomic.in_cluster(cluster),
+            warnings,
+        );
+    }
+}
+
+/// Finds the atomic helpers that are enabled in the configuration but cannot be
+/// generated, so they are not silently left out.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A description of the missing helpers of every register, empty if the helpers are
+/// disabled.
+pub fn find_missing(pac: &PeripheralAccess, config: &Config) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !config.atomic_helpers {
+        return warnings;
+    }
+
+    for peripheral in &pac.peripherals {
+        check_block(
+            &peripheral.name,
+            &peripheral.registers,
+            peripheral.clusters(),
+            &AtomicAccess::new(pac, peripheral, config),
+            &mut warnings,
+        );
+    }
+
+    warnings
+}
@@ -0,0 +1,56 @@
+//! The gen::build_rs module generates the build script of the peripheral access crate.
+//!
+//! The build script puts the generated linker scripts on the linker search path of the
+//! final binary: `device.x` when the `rt` feature is enabled, and `memory.x` when the
+//! `memory-x` feature is enabled.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{interrupts::collect_interrupts, memory::has_memory_regions};
+use crate::parse::PeripheralAccess;
+
+/// Generates the build script of the peripheral access crate.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// A TokenStream representing the build script, or None if no linker scripts are generated.
+pub fn gen_build_rs(pac: &PeripheralAccess) -> Option<TokenStream> {
+    let mut linker_scripts = Vec::new();
+    if !collect_interrupts(pac).is_empty() {
+        linker_scripts.push(gen_copy_linker_script("rt", "device.x"));
+    }
+    if has_memory_regions(pac) {
+        linker_scripts.push(gen_copy_linker_script("memory-x", "memory.x"));
+    }
+
+    if linker_scripts.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        use std::{env, fs, path::PathBuf};
+
+        fn main() {
+            let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+            #(#linker_scripts)*
+            println!("cargo:rerun-if-changed=build.rs");
+        }
+    })
+}
+
+/// Generates the statements that copy a linker script to `OUT_DIR` when a feature is enabled.
+fn gen_copy_linker_script(feature: &str, file_name: &str) -> TokenStream {
+    let feature_var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    let rerun_if_changed = format!("cargo:rerun-if-changed={}", file_name);
+
+    quote! {
+        if env::var_os(#feature_var).is_some() {
+            fs::copy(#file_name, out_dir.join(#file_name)).unwrap();
+            println!("cargo:rustc-link-search={}", out_dir.display());
+            println!(#rerun_if_changed);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! The gen::cache module keeps the generated files of every peripheral between generator
+//! runs, so a long running process such as the watch mode only regenerates the modules
+//! whose definitions changed. A peripheral is identified by its name, and its definition
+//! by a fingerprint of everything its modules are generated from.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    config::Config,
+    parse::{Peripheral, PeripheralAccess},
+};
+
+use super::GeneratedFile;
+
+/// The ModuleCache struct holds the generated files of the peripherals of the last run.
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    modules: HashMap<String, (u64, Vec<GeneratedFile>)>,
+    /// The peripherals that were generated, instead of taken from the cache, in the last run.
+    pub regenerated: Vec<String>,
+}
+
+impl ModuleCache {
+    /// Computes the fingerprint of the definition of a peripheral.
+    ///
+    /// # Arguments
+    /// pac: &PeripheralAccess - The parsed peripheral access data structure.
+    /// peripheral: &Peripheral - The peripheral.
+    /// config: &Config - The configuration the modules are generated with.
+    ///
+    /// # Returns
+    /// A hash that changes whenever the generated modules of the peripheral may change.
+    pub fn fingerprint(pac: &PeripheralAccess, peripheral: &Peripheral, config: &Config) -> u64 {
+        // The parsed structures have no Hash implementation, but their Debug output
+        // contains every field. Every bit-band region is included, since the atomic helpers
+        // look up the region of each register rather than of the peripheral.
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", peripheral).hash(&mut hasher);
+        format!("{:?}", config).hash(&mut hasher);
+        format!("{:?}", pac.bit_band_regions).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the cached files of a peripheral, or generates them if its definition changed.
+    ///
+    /// # Arguments
+    /// name: &str - The name of the peripheral.
+    /// fingerprint: u64 - The fingerprint of its current definition.
+    /// generate: impl FnOnce() -> Vec<GeneratedFile> - Generates the files of the peripheral.
+    ///
+    /// # Returns
+    /// The generated files of the peripheral.
+    pub fn get_or_generate(
+        &mut self,
+        name: &str,
+        fingerprint: u64,
+        generate: impl FnOnce() -> Vec<GeneratedFile>,
+    ) -> Vec<GeneratedFile> {
+        match self.modules.get(name) {
+            Some((cached, files)) if *cached == fingerprint => files.clone(),
+            _ => {
+                let files = generate();
+                self.modules
+                    .insert(name.to_string(), (fingerprint, files.clone()));
+                self.regenerated.push(name.to_string());
+                files
+            }
+        }
+    }
+
+    /// Starts a new run, forgetting which peripherals were regenerated and dropping the
+    /// peripherals that no longer exist.
+    ///
+    /// # Arguments
+    /// pac: &PeripheralAccess - The parsed peripheral access data structure of the new run.
+    pub fn start_run(&mut self, pac: &PeripheralAccess) {
+        self.regenerated.clear();
+        self.modules.retain(|name, _| {
+            pac.peripherals
+                .iter()
+                .any(|peripheral| &peripheral.name == name)
+        });
+    }
+}
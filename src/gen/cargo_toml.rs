@@ -0,0 +1,172 @@
+//! The gen::cargo_toml module generates the Cargo.toml file of the peripheral access crate.
+//!
+//! Since the Cargo.toml file is not a Rust source file, it is built line by line as a String.
+//! Values are written through toml::Value so strings and inline tables are quoted correctly.
+
+use std::collections::BTreeMap;
+
+use toml::Value;
+
+use super::{interrupts::collect_interrupts, memory::has_memory_regions};
+use crate::{config::Config, parse::PeripheralAccess};
+
+/// Generates a Cargo.toml file for the peripheral access crate.
+///
+/// # Arguments
+/// crate_name: &str - The name of the crate.
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config: &Config - The configuration file that contains the package metadata and dependencies.
+///
+/// # Returns
+/// The contents of the generated Cargo.toml file.
+pub fn gen_cargo_toml(crate_name: &str, pac: &PeripheralAccess, config: &Config) -> String {
+    let has_interrupts = !collect_interrupts(pac).is_empty();
+    let package = &config.package;
+    let mut lines = vec!["[package]".to_string()];
+
+    lines.push(format!("name = {}", toml_string(crate_name)));
+    lines.push(format!("version = {}", toml_string(&package.version)));
+    lines.push(format!("edition = {}", toml_string(&package.edition)));
+
+    if !package.authors.is_empty() {
+        let authors = Value::Array(package.authors.iter().map(|a| toml_value(a)).collect());
+        lines.push(format!("authors = {}", authors));
+    }
+
+    let optional_keys = [
+        ("description", &package.description),
+        ("license", &package.license),
+        ("repository", &package.repository),
+        ("rust-version", &package.rust_version),
+    ];
+    for (key, value) in optional_keys {
+        if let Some(value) = value {
+            lines.push(format!("{} = {}", key, toml_string(value)));
+        }
+    }
+
+    // Crates without dependencies carry their own volatile and bit manipulation code.
+    // The dependencies of the configuration file are merged in by name, overriding the
+    // generated ones, like the features below.
+    let mut dependencies = BTreeMap::new();
+    if !config.zero_dependencies {
+        dependencies.insert("bit_field", toml_string(&config.bit_field_version));
+        dependencies.insert(
+            "volatile-register",
+            toml_string(&config.volatile_register_version),
+        );
+    }
+    if has_interrupts && !config.zero_dependencies {
+        dependencies.insert(
+            "cortex-m",
+            format!(
+                "{{ version = {}, optional = true }}",
+                toml_string(&config.cortex_m_version)
+            ),
+        );
+    }
+    // The builders and enums implement defmt::Format when the defmt feature is enabled
+    if !config.zero_dependencies {
+        dependencies.insert(
+            "defmt",
+            format!(
+                "{{ version = {}, optional = true }}",
+                toml_string(&config.defmt_version)
+            ),
+        );
+    }
+    // The builders, enums and snapshots implement Serialize and Deserialize when the serde
+    // feature is enabled
+    if !config.zero_dependencies {
+        dependencies.insert(
+            "serde",
+            format!(
+                "{{ version = {}, optional = true, default-features = false, features = [\"derive\"] }}",
+                toml_string(&config.serde_version)
+            ),
+        );
+    }
+    // The linux feature maps the registers with mmap
+    if !config.zero_dependencies {
+        dependencies.insert(
+            "libc",
+            format!(
+                "{{ version = {}, optional = true }}",
+                toml_string(&config.libc_version)
+            ),
+        );
+    }
+    // The atomic helpers fall back to a critical section when there are no alias registers
+    if config.atomic_helpers && !config.zero_dependencies {
+        dependencies.insert(
+            "critical-section",
+            format!(
+                "{{ version = {}, optional = true }}",
+                toml_string(&config.critical_section_version)
+            ),
+        );
+    }
+    for (name, value) in &config.dependencies {
+        dependencies.insert(name, value.to_string());
+    }
+
+    lines.push(String::new());
+    lines.push("[dependencies]".to_string());
+    for (name, value) in &dependencies {
+        lines.push(format!("{} = {}", name, value));
+    }
+
+    // The rt feature enables the interrupt vector table, the memory-x feature exports
+    // memory.x to the linker and the linux feature maps the registers under Linux, unless
+    // the user defines them
+    let mut features = config.features.clone();
+    if has_interrupts {
+        features.entry("rt".to_string()).or_default();
+    }
+    if has_memory_regions(pac) {
+        features.entry("memory-x".to_string()).or_default();
+    }
+    if !config.zero_dependencies {
+        features
+            .entry("linux".to_string())
+            .or_insert_with(|| vec!["dep:libc".to_string()]);
+    }
+
+    if !features.is_empty() {
+        lines.push(String::new());
+        lines.push("[features]".to_string());
+        for (name, enables) in &features {
+            let enables = Value::Array(enables.iter().map(|f| toml_value(f)).collect());
+            lines.push(format!("{} = {}", name, enables));
+        }
+    }
+
+    // The metadata is free-form and can contain nested tables, so let the toml crate lay it out
+    if let Some(metadata) = &package.metadata {
+        let mut package_table = toml::Table::new();
+        package_table.insert("metadata".to_string(), Value::Table(metadata.clone()));
+        let mut root = toml::Table::new();
+        root.insert("package".to_string(), Value::Table(package_table));
+
+        lines.push(String::new());
+        lines.push(
+            toml::to_string(&root)
+                .expect("Failed to serialize package metadata")
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Converts a string into a TOML string value.
+fn toml_value(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+/// Formats a string as a quoted TOML string.
+fn toml_string(value: &str) -> String {
+    toml_value(value).to_string()
+}
@@ -0,0 +1,154 @@
+//! The gen::debug module generates the formatting impls of the register builders and
+//! of the field enums.
+//!
+//! Instead of the raw value, the builders are formatted field by field, with enum fields
+//! decoded to their variant, e.g. `ModeBuilder { clk_sel: false, char_len: EightBits, .. }`.
+//! The same layout is used for `core::fmt::Debug` and, behind the `defmt` feature of the
+//! generated crate, for `defmt::Format`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::ident::{pascal_ident, snake_ident};
+use super::naming;
+use super::utils::bit_range_mask;
+use crate::{
+    config::Naming,
+    parse::{utils::Access, Enum, Field, Register},
+};
+
+/// Returns the fields of a register that have a getter on the builder.
+fn readable_fields(register: &Register) -> impl Iterator<Item = &Field> {
+    register.fields.iter().flatten().filter(|field| {
+        matches!(
+            field.access.as_ref().unwrap_or(&Access::ReadWrite),
+            Access::ReadOnly | Access::ReadWrite
+        )
+    })
+}
+
+/// Returns the expression for the raw bits of a field, shown when an enum field
+/// holds a value that does not match any variant.
+fn raw_field_bits(field: &Field) -> TokenStream {
+    let (start, end) = field.bit_range.get_bit_range();
+    let mask = bit_range_mask(start, end);
+    quote! { (self.value & #mask) >> #start }
+}
+
+/// Generates the Debug impl of a register builder, listing every readable field.
+///
+/// # Arguments
+/// register: &Register - The register to generate the impl for. It must have fields.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the generated impl.
+pub fn gen_builder_debug(register: &Register, naming: &Naming) -> TokenStream {
+    let register_builder_name = naming::builder_type(&register.name, naming);
+    let builder_name_str = register_builder_name.to_string();
+
+    let fields = readable_fields(register).map(|field| {
+        let field_name = snake_ident(&field.name);
+        let field_name_str = field_name.to_string();
+
+        if field.enums.is_some() {
+            let raw_bits = raw_field_bits(field);
+            quote! {
+                match self.#field_name() {
+                    Some(value) => s.field(#field_name_str, &value),
+                    None => s.field(#field_name_str, &(#raw_bits)),
+                };
+            }
+        } else {
+            quote! {
+                s.field(#field_name_str, &self.#field_name());
+            }
+        }
+    });
+
+    quote! {
+        impl core::fmt::Debug for #register_builder_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let mut s = f.debug_struct(#builder_name_str);
+                #(#fields)*
+                s.finish()
+            }
+        }
+    }
+}
+
+/// Generates the defmt::Format impl of a register builder, behind the `defmt` feature.
+/// It prints the same fields as the Debug impl.
+///
+/// # Arguments
+/// register: &Register - The register to generate the impl for. It must have fields.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the generated impl.
+pub fn gen_builder_defmt(register: &Register, naming: &Naming) -> TokenStream {
+    let register_builder_name = naming::builder_type(&register.name, naming);
+    let opening = format!("{} {{{{ ", register_builder_name);
+
+    let fields = readable_fields(register).enumerate().map(|(i, field)| {
+        let field_name = snake_ident(&field.name);
+        let separator = if i == 0 { "" } else { ", " };
+        let format_str = format!("{}{}: {{}}", separator, field_name);
+
+        if field.enums.is_some() {
+            let raw_bits = raw_field_bits(field);
+            quote! {
+                match self.#field_name() {
+                    Some(value) => defmt::write!(f, #format_str, value),
+                    None => defmt::write!(f, #format_str, #raw_bits),
+                }
+            }
+        } else {
+            quote! {
+                defmt::write!(f, #format_str, self.#field_name());
+            }
+        }
+    });
+
+    quote! {
+        #[cfg(feature = "defmt")]
+        impl defmt::Format for #register_builder_name {
+            fn format(&self, f: defmt::Formatter) {
+                defmt::write!(f, #opening);
+                #(#fields)*
+                defmt::write!(f, " }}");
+            }
+        }
+    }
+}
+
+/// Generates the defmt::Format impl of a field enum, behind the `defmt` feature. Like
+/// the Debug derive, it prints the name of the variant.
+///
+/// # Arguments
+/// enum_def: &Enum - The enum to generate the impl for.
+///
+/// # Returns
+/// A TokenStream representing the generated impl.
+pub fn gen_enum_defmt(enum_def: &Enum) -> TokenStream {
+    let enum_name = pascal_ident(&enum_def.name);
+
+    let arms = enum_def.values.iter().map(|value| {
+        let variant_name = pascal_ident(&value.name);
+        let variant_name_str = variant_name.to_string();
+        quote! {
+            Self::#variant_name => defmt::write!(f, #variant_name_str),
+        }
+    });
+
+    quote! {
+        #[cfg(feature = "defmt")]
+        impl defmt::Format for #enum_name {
+            fn format(&self, f: defmt::Formatter) {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
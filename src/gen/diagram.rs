@@ -0,0 +1,153 @@
+//! The gen::diagram module draws the bit layout of a register as ASCII art, the way
+//! datasheets do, with the fields from the most significant bit to the least significant
+//! one and the bits that no field covers marked as reserved:
+//!
+//! ```text
+//!  31      10 9            8 7             6 5      3 2        1     0
+//! +----------+--------------+---------------+--------+----------+---------+
+//! | reserved | channel_mode | num_stop_bits | parity | char_len | clk_sel |
+//! +----------+--------------+---------------+--------+----------+---------+
+//! ```
+
+use crate::parse::Register;
+
+/// The maximum width of a line of the diagram. Wider layouts are split into several rows.
+const MAX_WIDTH: usize = 100;
+
+/// A field or a run of reserved bits in the diagram.
+struct Segment {
+    label: String,
+    msb: usize,
+    lsb: usize,
+}
+
+impl Segment {
+    /// The bit numbers printed above the segment.
+    fn header(&self, width: usize) -> String {
+        if self.msb == self.lsb {
+            format!("{:^width$}", self.msb)
+        } else {
+            let msb = self.msb.to_string();
+            let lsb = self.lsb.to_string();
+            let gap = width - msb.len() - lsb.len();
+            format!("{}{}{}", msb, " ".repeat(gap), lsb)
+        }
+    }
+
+    /// The width of the segment, without its borders.
+    fn width(&self) -> usize {
+        let header_width = if self.msb == self.lsb {
+            self.msb.to_string().len()
+        } else {
+            self.msb.to_string().len() + self.lsb.to_string().len() + 1
+        };
+        self.label.len().max(header_width) + 2
+    }
+}
+
+/// Splits a register into its fields and reserved runs, from the most significant bit.
+/// Fields that overlap a previous field are left out.
+fn segments(register: &Register) -> Vec<Segment> {
+    let mut fields = register
+        .fields
+        .iter()
+        .flatten()
+        .map(|field| (field.name.as_str(), field.bit_range.get_bit_range()))
+        .collect::<Vec<_>>();
+    fields.sort_by_key(|(_, (_, end))| std::cmp::Reverse(*end));
+
+    let mut segments = Vec::new();
+    // The next bit to draw, plus one so it can reach zero
+    let mut next = register.size;
+    for (name, (start, end)) in fields {
+        if end >= next {
+            continue;
+        }
+        if end + 1 < next {
+            segments.push(Segment {
+                label: "reserved".to_string(),
+                msb: next - 1,
+                lsb: end + 1,
+            });
+        }
+        segments.push(Segment {
+            label: name.to_string(),
+            msb: end,
+            lsb: start,
+        });
+        next = start;
+    }
+    if next > 0 {
+        segments.push(Segment {
+            label: "reserved".to_string(),
+            msb: next - 1,
+            lsb: 0,
+        });
+    }
+
+    segments
+}
+
+/// Draws the bit layout of a register.
+///
+/// # Arguments
+/// register: &Register - The register to draw.
+///
+/// # Returns
+/// The diagram, without a trailing newline, or None if the register has no fields.
+pub fn bit_field_diagram(register: &Register) -> Option<String> {
+    if register
+        .fields
+        .as_ref()
+        .is_none_or(|fields| fields.is_empty())
+    {
+        return None;
+    }
+
+    // Pack the segments into rows that fit in the maximum width
+    let mut rows: Vec<Vec<Segment>> = Vec::new();
+    let mut row_width = 0;
+    for segment in segments(register) {
+        let width = segment.width() + 1;
+        match rows.last_mut() {
+            Some(row) if row_width + width < MAX_WIDTH => row.push(segment),
+            _ => {
+                rows.push(vec![segment]);
+                row_width = 0;
+            }
+        }
+        row_width += width;
+    }
+
+    let diagram = rows
+        .iter()
+        .map(|row| {
+            let widths = row.iter().map(Segment::width).collect::<Vec<_>>();
+            let headers = row
+                .iter()
+                .zip(&widths)
+                .map(|(segment, width)| segment.header(*width))
+                .collect::<Vec<_>>();
+            let labels = row
+                .iter()
+                .zip(&widths)
+                .map(|(segment, width)| format!("{:^width$}", segment.label))
+                .collect::<Vec<_>>();
+            let border = widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("+");
+
+            format!(
+                " {}\n+{}+\n|{}|\n+{}+",
+                headers.join(" ").trim_end(),
+                border,
+                labels.join("|"),
+                border
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Some(diagram.join("\n\n"))
+}
@@ -0,0 +1,528 @@
+//! The gen::docs module renders a register reference from the description, as static
+//! Markdown or HTML pages that can be published without rustdoc.
+//!
+//! The reference is made of an `index` page with the address map of the peripherals, the
+//! memory regions and an index of every register, and a page per peripheral with its
+//! interrupts, a table of its registers and a section per register with its bit-field
+//! diagram and a table of its fields (access, reset value and enum values).
+
+use std::fmt::Write;
+
+use super::{diagram::bit_field_diagram, openocd::bits_label, GeneratedFile};
+use crate::parse::{block_size, Cluster, Field, Peripheral, PeripheralAccess, Register};
+
+/// The styles of the HTML pages.
+const HTML_STYLE: &str =
+    "body { font-family: sans-serif; max-width: 72em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+pre { background: #f6f6f6; padding: 0.8em; overflow-x: auto; }
+code { font-size: 0.95em; }";
+
+/// The DocsFormat enum is the output format of the register reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+impl DocsFormat {
+    /// The extension of the pages.
+    fn extension(self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Html => "html",
+        }
+    }
+
+    /// Escapes text from the description so it can be used in a page or a table cell.
+    fn escape(self, text: &str) -> String {
+        let text = text.replace('\n', " ");
+        match self {
+            DocsFormat::Markdown => text
+                .replace('|', "\\|")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            DocsFormat::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+        }
+    }
+
+    /// Formats inline code.
+    fn code(self, text: &str) -> String {
+        match self {
+            DocsFormat::Markdown => format!("`{}`", text),
+            DocsFormat::Html => format!("<code>{}</code>", self.escape(text)),
+        }
+    }
+
+    /// Formats a link to a page or an anchor. The text must already be formatted.
+    fn link(self, text: &str, target: &str) -> String {
+        match self {
+            DocsFormat::Markdown => format!("[{}]({})", text, target),
+            DocsFormat::Html => format!("<a href=\"{}\">{}</a>", target, text),
+        }
+    }
+
+    /// Formats a heading, with an anchor that can be linked to.
+    fn heading(self, level: usize, text: &str, anchor: Option<&str>) -> String {
+        match (self, anchor) {
+            (DocsFormat::Markdown, Some(anchor)) => {
+                format!(
+                    "<a id=\"{}\"></a>\n\n{} {}\n\n",
+                    anchor,
+                    "#".repeat(level),
+                    text
+                )
+            }
+            (DocsFormat::Markdown, None) => format!("{} {}\n\n", "#".repeat(level), text),
+            (DocsFormat::Html, Some(anchor)) => {
+                format!("<h{0} id=\"{1}\">{2}</h{0}>\n", level, anchor, text)
+            }
+            (DocsFormat::Html, None) => format!("<h{0}>{1}</h{0}>\n", level, text),
+        }
+    }
+
+    /// Formats a paragraph. The text must already be formatted.
+    fn paragraph(self, text: &str) -> String {
+        match self {
+            DocsFormat::Markdown => format!("{}\n\n", text),
+            DocsFormat::Html => format!("<p>{}</p>\n", text),
+        }
+    }
+
+    /// Formats preformatted text, such as a bit-field diagram.
+    fn preformatted(self, text: &str) -> String {
+        match self {
+            DocsFormat::Markdown => format!("```text\n{}\n```\n\n", text),
+            DocsFormat::Html => format!("<pre>{}</pre>\n", self.escape_preformatted(text)),
+        }
+    }
+
+    /// Escapes preformatted text, keeping its line breaks.
+    fn escape_preformatted(self, text: &str) -> String {
+        text.lines()
+            .map(|line| self.escape(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Formats a table. The cells must already be formatted.
+    fn table(self, headers: &[&str], rows: &[Vec<String>]) -> String {
+        let mut table = String::new();
+        match self {
+            DocsFormat::Markdown => {
+                let _ = writeln!(table, "| {} |", headers.join(" | "));
+                let _ = writeln!(table, "|{}", "---|".repeat(headers.len()));
+                for row in rows {
+                    let _ = writeln!(table, "| {} |", row.join(" | "));
+                }
+                table.push('\n');
+            }
+            DocsFormat::Html => {
+                table.push_str("<table>\n<tr>");
+                for header in headers {
+                    let _ = write!(table, "<th>{}</th>", header);
+                }
+                table.push_str("</tr>\n");
+                for row in rows {
+                    table.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(table, "<td>{}</td>", cell);
+                    }
+                    table.push_str("</tr>\n");
+                }
+                table.push_str("</table>\n");
+            }
+        }
+        table
+    }
+
+    /// Wraps the body of a page into a complete document.
+    fn page(self, title: &str, body: &str) -> String {
+        match self {
+            DocsFormat::Markdown => body.to_string(),
+            DocsFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                self.escape(title),
+                HTML_STYLE,
+                body
+            ),
+        }
+    }
+}
+
+/// A register definition documented in a section of a peripheral page. The registers of
+/// cluster arrays are documented once for every element.
+struct RegisterSection<'a> {
+    /// The title of the section, e.g. "MODE" or "CH[n].CFG"
+    title: String,
+    anchor: String,
+    register: &'a Register,
+}
+
+/// Collects the register definitions of a register block and its clusters.
+fn collect_sections<'a>(
+    registers: &'a [Register],
+    clusters: &'a [Cluster],
+    prefix: &str,
+    sections: &mut Vec<RegisterSection<'a>>,
+) {
+    for register in registers {
+        let title = format!("{}{}", prefix, register.name);
+        sections.push(RegisterSection {
+            anchor: anchor(&title),
+            title,
+            register,
+        });
+    }
+
+    for cluster in clusters {
+        let prefix = match cluster.count {
+            Some(_) => format!("{}{}[n].", prefix, cluster.name),
+            None => format!("{}{}.", prefix, cluster.name),
+        };
+        collect_sections(&cluster.registers, cluster.clusters(), &prefix, sections);
+    }
+}
+
+/// Returns the anchor of a title, in lower case with every other character replaced by `-`.
+fn anchor(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| *c != '[' && *c != ']')
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Returns the file name of the page of a peripheral.
+fn peripheral_page(peripheral: &Peripheral, format: DocsFormat) -> String {
+    format!("{}.{}", anchor(&peripheral.name), format.extension())
+}
+
+/// Formats a register value in hexadecimal, padded to the size of the register.
+fn hex(value: u64, size: usize) -> String {
+    format!("0x{:0width$X}", value, width = size / 4)
+}
+
+/// Generates the register reference of the peripheral access crate.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// format: DocsFormat - The format of the pages.
+///
+/// # Returns
+/// The pages of the reference: `index.<ext>` and a page per peripheral.
+pub fn gen_docs(pac: &PeripheralAccess, format: DocsFormat) -> Vec<GeneratedFile> {
+    let mut files = vec![GeneratedFile::new(
+        format!("index.{}", format.extension()),
+        gen_index_page(pac, format),
+    )];
+
+    for peripheral in &pac.peripherals {
+        files.push(GeneratedFile::new(
+            peripheral_page(peripheral, format),
+            gen_peripheral_page(pac, peripheral, format),
+        ));
+    }
+
+    files
+}
+
+/// Generates the index page, with the address map, the memory regions and an index of
+/// every register.
+fn gen_index_page(pac: &PeripheralAccess, format: DocsFormat) -> String {
+    let title = format!("{} register reference", pac.name);
+    let mut body = format.heading(1, &format.escape(&title), None);
+
+    // The address map of the peripherals
+    let mut peripherals = pac.peripherals.iter().collect::<Vec<_>>();
+    peripherals.sort_by_key(|peripheral| peripheral.base_address);
+    let rows = peripherals
+        .iter()
+        .map(|peripheral| {
+            let size = block_size(&peripheral.registers, peripheral.clusters());
+            let interrupts = peripheral
+                .interrupts
+                .iter()
+                .flatten()
+                .map(|interrupt| {
+                    format!("{} ({})", format.escape(&interrupt.name), interrupt.number)
+                })
+                .collect::<Vec<_>>();
+            vec![
+                format.link(
+                    &format.escape(&peripheral.name),
+                    &peripheral_page(peripheral, format),
+                ),
+                format.code(&format!("0x{:08X}", peripheral.base_address)),
+                format.code(&format!(
+                    "0x{:08X}",
+                    (peripheral.base_address + size).saturating_sub(1)
+                )),
+                format!("0x{:X}", size),
+                peripheral.flat_registers().len().to_string(),
+                interrupts.join(", "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    body.push_str(&format.heading(2, "Address map", Some("address-map")));
+    body.push_str(&format.table(
+        &[
+            "Peripheral",
+            "Start",
+            "End",
+            "Size",
+            "Registers",
+            "Interrupts",
+        ],
+        &rows,
+    ));
+
+    if let Some(memory_regions) = pac.memory_regions.as_ref().filter(|r| !r.is_empty()) {
+        let rows = memory_regions
+            .iter()
+            .map(|region| {
+                vec![
+                    format.escape(&region.name),
+                    format.code(&format!("0x{:08X}", region.origin)),
+                    format!("0x{:X}", region.length),
+                    format.escape(region.attributes.as_deref().unwrap_or_default()),
+                    format.escape(region.description.as_deref().unwrap_or_default()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        body.push_str(&format.heading(2, "Memory regions", Some("memory-regions")));
+        body.push_str(&format.table(
+            &["Region", "Origin", "Length", "Attributes", "Description"],
+            &rows,
+        ));
+    }
+
+    // An index of every register, sorted by name, for looking registers up
+    let mut registers = Vec::new();
+    for peripheral in &pac.peripherals {
+        let mut sections = Vec::new();
+        collect_sections(
+            &peripheral.registers,
+            peripheral.clusters(),
+            "",
+            &mut sections,
+        );
+        for section in sections {
+            registers.push((
+                format!("{}.{}", peripheral.name, section.title),
+                format!("{}#{}", peripheral_page(peripheral, format), section.anchor),
+                section.register,
+            ));
+        }
+    }
+    registers.sort_by(|a, b| a.0.cmp(&b.0));
+    let rows = registers
+        .iter()
+        .map(|(path, target, register)| {
+            vec![
+                format.link(&format.escape(path), target),
+                format.escape(&register.description),
+            ]
+        })
+        .collect::<Vec<_>>();
+    body.push_str(&format.heading(2, "Register index", Some("register-index")));
+    body.push_str(&format.table(&["Register", "Description"], &rows));
+
+    format.page(&title, &body)
+}
+
+/// Generates the page of a peripheral.
+fn gen_peripheral_page(
+    pac: &PeripheralAccess,
+    peripheral: &Peripheral,
+    format: DocsFormat,
+) -> String {
+    let size = block_size(&peripheral.registers, peripheral.clusters());
+    let mut body = format.heading(1, &format.escape(&peripheral.name), None);
+    body.push_str(&format.paragraph(&format!(
+        "{} of {}. Base address {}, size 0x{:X} bytes.",
+        format.link(
+            "Address map",
+            &format!("index.{}#address-map", format.extension())
+        ),
+        format.escape(&pac.name),
+        format.code(&format!("0x{:08X}", peripheral.base_address)),
+        size
+    )));
+
+    if let Some(interrupts) = peripheral.interrupts.as_ref().filter(|i| !i.is_empty()) {
+        let rows = interrupts
+            .iter()
+            .map(|interrupt| {
+                vec![
+                    format.escape(&interrupt.name),
+                    interrupt.number.to_string(),
+                    format.escape(&interrupt.description),
+                ]
+            })
+            .collect::<Vec<_>>();
+        body.push_str(&format.heading(2, "Interrupts", Some("interrupts")));
+        body.push_str(&format.table(&["Interrupt", "Number", "Description"], &rows));
+    }
+
+    let mut sections = Vec::new();
+    collect_sections(
+        &peripheral.registers,
+        peripheral.clusters(),
+        "",
+        &mut sections,
+    );
+    let section_anchor = |register: &Register| {
+        sections
+            .iter()
+            .find(|section| std::ptr::eq(section.register, register))
+            .map(|section| section.anchor.clone())
+            .unwrap_or_default()
+    };
+
+    // The registers of the peripheral, with every element of the cluster arrays
+    let flat_registers = peripheral.flat_registers();
+    let rows = flat_registers
+        .iter()
+        .map(|flat_register| {
+            let register = flat_register.register;
+            vec![
+                format.code(&format!("0x{:X}", flat_register.offset)),
+                format.code(&format!(
+                    "0x{:08X}",
+                    peripheral.base_address + flat_register.offset
+                )),
+                format.link(
+                    &format.escape(&flat_register.path),
+                    &format!("#{}", section_anchor(register)),
+                ),
+                register.access.as_str().to_string(),
+                format.code(&hex(register.reset_value(), register.size)),
+                format.escape(&register.description),
+            ]
+        })
+        .collect::<Vec<_>>();
+    body.push_str(&format.heading(2, "Registers", Some("registers")));
+    body.push_str(&format.table(
+        &[
+            "Offset",
+            "Address",
+            "Register",
+            "Access",
+            "Reset value",
+            "Description",
+        ],
+        &rows,
+    ));
+
+    for section in &sections {
+        body.push_str(&gen_register_section(
+            peripheral,
+            section,
+            &flat_registers,
+            format,
+        ));
+    }
+
+    format.page(&peripheral.name, &body)
+}
+
+/// Generates the section of a register, with its addresses, bit-field diagram and fields.
+fn gen_register_section(
+    peripheral: &Peripheral,
+    section: &RegisterSection,
+    flat_registers: &[crate::parse::FlatRegister],
+    format: DocsFormat,
+) -> String {
+    let register = section.register;
+    let mut text = format.heading(2, &format.escape(&section.title), Some(&section.anchor));
+    text.push_str(&format.paragraph(&format.escape(&register.description)));
+
+    let addresses = flat_registers
+        .iter()
+        .filter(|flat_register| std::ptr::eq(flat_register.register, register))
+        .map(|flat_register| {
+            let address = format.code(&format!(
+                "0x{:08X}",
+                peripheral.base_address + flat_register.offset
+            ));
+            if section.title.contains("[n]") {
+                format!("{} ({})", address, format.escape(&flat_register.path))
+            } else {
+                address
+            }
+        })
+        .collect::<Vec<_>>();
+    text.push_str(&format.paragraph(&format!(
+        "{} {}. Access {}, {} bits, reset value {}. {}",
+        if addresses.len() > 1 {
+            "Addresses"
+        } else {
+            "Address"
+        },
+        addresses.join(", "),
+        register.access.as_str(),
+        register.size,
+        format.code(&hex(register.reset_value(), register.size)),
+        format.link("Back to the registers", "#registers")
+    )));
+
+    if let Some(diagram) = bit_field_diagram(register) {
+        text.push_str(&format.preformatted(&diagram));
+    }
+
+    if let Some(fields) = register.fields.as_ref().filter(|f| !f.is_empty()) {
+        let mut fields = fields.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|field| std::cmp::Reverse(field.bit_range.get_end_bit()));
+        let rows = fields
+            .iter()
+            .map(|field| {
+                vec![
+                    bits_label(field),
+                    format.code(&field.name),
+                    field
+                        .access
+                        .as_ref()
+                        .unwrap_or(&register.access)
+                        .as_str()
+                        .to_string(),
+                    register
+                        .field_reset(field)
+                        .map(|bits| format!("0x{:X}", bits))
+                        .unwrap_or_else(|| "-".to_string()),
+                    field_description(field, format),
+                ]
+            })
+            .collect::<Vec<_>>();
+        text.push_str(&format.table(&["Bits", "Field", "Access", "Reset", "Description"], &rows));
+    }
+
+    text
+}
+
+/// Returns the description of a field, followed by the meaning of its enum values.
+fn field_description(field: &Field, format: DocsFormat) -> String {
+    let mut description = format.escape(&field.description);
+    for value in field.enums.iter().flat_map(|enum_def| &enum_def.values) {
+        let _ = write!(
+            description,
+            "<br>{} {}: {}",
+            format.code(&format!("0x{:X}", value.value)),
+            format.escape(&value.name),
+            format.escape(&value.description)
+        );
+    }
+    description
+}
@@ -0,0 +1,148 @@
+//! The gen::gdb module generates a GDB Python script with a `pac show` command, which
+//! reads registers from the target and prints their decoded fields:
+//!
+//! ```text
+//! (gdb) source zynq_pac-gdb.py
+//! (gdb) pac show UART.MODE
+//! UART.MODE @ 0xE0001004 = 0x00000020
+//!   clk_sel              [0]     = 0x0
+//!   char_len             [2:1]   = 0x0 (EightBits)
+//! ```
+//!
+//! `pac show UART` prints every readable register of the peripheral.
+
+use std::fmt::Write;
+
+use super::openocd::{bits_label, readable_fields};
+use crate::parse::{utils::Access, PeripheralAccess};
+
+/// The command implementation, which works on the REGISTERS table of the script.
+const COMMANDS: &str = r#"
+
+def read_register(address, size):
+    """Reads a register from the target, in the byte order of the target."""
+    data = bytes(gdb.selected_inferior().read_memory(address, size // 8))
+    byteorder = "big" if "big endian" in gdb.execute("show endian", to_string=True) else "little"
+    return int.from_bytes(data, byteorder)
+
+
+def show_register(path):
+    address, size, _description, fields = REGISTERS[path]
+    value = read_register(address, size)
+    print("{} @ 0x{:08X} = 0x{:0{}X}".format(path, address, value, size // 4))
+    for name, bits, lsb, width, variants in fields:
+        field = (value >> lsb) & ((1 << width) - 1)
+        line = "  {:<20} {:<7} = 0x{:X}".format(name, bits, field)
+        if field in variants:
+            line += " ({})".format(variants[field])
+        print(line)
+
+
+class PacCommand(gdb.Command):
+    """Peripheral access commands generated by rust-pac-gen."""
+
+    def __init__(self):
+        super().__init__("pac", gdb.COMMAND_DATA, gdb.COMPLETE_NONE, True)
+
+
+class PacShowCommand(gdb.Command):
+    """Reads a register and prints its decoded fields.
+
+Usage: pac show PERIPHERAL.REGISTER
+       pac show PERIPHERAL (every readable register of the peripheral)"""
+
+    def __init__(self):
+        super().__init__("pac show", gdb.COMMAND_DATA)
+
+    def invoke(self, argument, from_tty):
+        path = argument.strip().upper()
+        if path in REGISTERS:
+            show_register(path)
+            return
+        matches = [name for name in REGISTERS if name.startswith(path + ".")]
+        if not path or not matches:
+            raise gdb.GdbError("unknown register or peripheral: {}".format(argument.strip()))
+        for name in matches:
+            show_register(name)
+
+    def complete(self, text, word):
+        return [name for name in REGISTERS if name.startswith(text.upper())]
+
+
+PacCommand()
+PacShowCommand()
+"#;
+
+/// Generates the GDB Python script for the peripheral access crate.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The contents of the Python script.
+pub fn gen_gdb_script(pac: &PeripheralAccess) -> String {
+    let mut script = String::new();
+    let _ = writeln!(
+        script,
+        "# GDB helpers for {}, generated by rust-pac-gen. Do not edit.",
+        pac.name
+    );
+    script.push_str(
+        "# Load with `source <path to this file>`, then run e.g. `pac show UART.MODE`.\n",
+    );
+    script.push_str("\nimport gdb\n\n");
+    script.push_str(
+        "# Every readable register: path -> (address, size in bits, description,\n\
+         #     [(field, bits, lsb, width, {value: variant})])\n",
+    );
+    script.push_str("REGISTERS = {\n");
+
+    for peripheral in &pac.peripherals {
+        for flat_register in peripheral.flat_registers() {
+            let register = flat_register.register;
+            if matches!(register.access, Access::WriteOnly) {
+                continue;
+            }
+
+            let path = format!("{}.{}", peripheral.name, flat_register.path).to_uppercase();
+            let address = peripheral.base_address + flat_register.offset;
+            let _ = writeln!(
+                script,
+                "    {}: (0x{:08X}, {}, {}, [",
+                python_string(&path),
+                address,
+                register.size,
+                python_string(&register.description)
+            );
+            for field in readable_fields(register) {
+                let (start, end) = field.bit_range.get_bit_range();
+                let variants = field
+                    .enums
+                    .iter()
+                    .flat_map(|enum_def| &enum_def.values)
+                    .map(|value| format!("{}: {}", value.value, python_string(&value.name)))
+                    .collect::<Vec<_>>();
+                let _ = writeln!(
+                    script,
+                    "        ({}, {}, {}, {}, {{{}}}),",
+                    python_string(&field.name),
+                    python_string(&bits_label(field)),
+                    start,
+                    end - start + 1,
+                    variants.join(", ")
+                );
+            }
+            script.push_str("    ]),\n");
+        }
+    }
+
+    script.push_str("}\n");
+    script.push_str(COMMANDS);
+    script
+}
+
+/// Quotes a string as a Python string literal.
+/// JSON string literals are valid Python string literals.
+fn python_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
@@ -0,0 +1,387 @@
+//! The gen::ident module turns the names of the description into valid Rust identifiers.
+//! Vendor data uses names that are keywords (`type`), start with a digit (`1WIRE`, `8Bits`)
+//! or contain characters such as `-`, `/` or `.`, so every generated identifier goes
+//! through here instead of being formatted directly:
+//!
+//! - characters other than ASCII letters, digits and `_` separate words, like `_` does,
+//! - a leading digit gets a `_` prefix (`_1_wire`, `_8Bits`),
+//! - keywords become raw identifiers (`r#type`), except the ones that cannot be raw
+//!   (`self`, `Self`, `super`, `crate`) and `mod`, which get a `_` suffix.
+//!
+//! Different names can end up as the same identifier after the case conversion (`TxEn` and
+//! `TX_EN`), which `find_collisions` detects before any code is generated.
+
+use std::collections::HashMap;
+
+use proc_macro2::{Ident, Span};
+use stringcase::Caser;
+
+use crate::parse::{Cluster, PeripheralAccess, Register};
+
+use super::interrupts::collect_interrupts;
+
+/// The strict and reserved keywords of every edition.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// The keywords that cannot be raw identifiers, and `mod`, since a module named `r#mod`
+/// would be looked up in the `mod.rs` file of its parent.
+const SUFFIXED_KEYWORDS: &[&str] = &["crate", "self", "Self", "super", "mod"];
+
+/// Replaces the characters that are not allowed in identifiers with word separators.
+fn replace_invalid_chars(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Converts a name to snake case, e.g. for modules, fields and methods.
+/// The result is not escaped yet, so it can be combined with other words first.
+pub fn snake(name: &str) -> String {
+    replace_invalid_chars(name).to_snake_case()
+}
+
+/// Converts a name to Pascal case, e.g. for types and enum variants.
+/// The result is not escaped yet, so it can be combined with other words first.
+pub fn pascal(name: &str) -> String {
+    replace_invalid_chars(name).to_pascal_case()
+}
+
+/// Converts a name to macro case, e.g. for constants.
+/// The result is not escaped yet, so it can be combined with other words first.
+pub fn macro_case(name: &str) -> String {
+    replace_invalid_chars(name).to_macro_case()
+}
+
+/// Makes an identifier out of a name that already has its final case.
+///
+/// # Arguments
+/// name: &str - The name, e.g. `type`, `8Bits` or `with_char_len`.
+///
+/// # Returns
+/// The identifier, escaped if the name is a keyword.
+pub fn ident(name: &str) -> Ident {
+    let mut name = replace_invalid_chars(name);
+    if name.is_empty() {
+        name = "unnamed".to_string();
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    if SUFFIXED_KEYWORDS.contains(&name.as_str()) {
+        Ident::new(&format!("{}_", name), Span::call_site())
+    } else if KEYWORDS.contains(&name.as_str()) {
+        Ident::new_raw(&name, Span::call_site())
+    } else {
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+/// Makes a snake case identifier out of a name.
+pub fn snake_ident(name: &str) -> Ident {
+    ident(&snake(name))
+}
+
+/// Makes a Pascal case identifier out of a name.
+pub fn pascal_ident(name: &str) -> Ident {
+    ident(&pascal(name))
+}
+
+/// Makes a macro case identifier out of a name.
+pub fn macro_ident(name: &str) -> Ident {
+    ident(&macro_case(name))
+}
+
+/// Returns the file or directory name of the module with the given name. It is the name
+/// of its identifier, without the `r#` of a raw identifier.
+pub fn module_file_name(name: &str) -> String {
+    snake_ident(name)
+        .to_string()
+        .trim_start_matches("r#")
+        .to_string()
+}
+
+/// Reports the names of a scope that end up as the same identifier.
+fn check_scope<'a>(
+    scope: &str,
+    names: impl IntoIterator<Item = &'a str>,
+    to_ident: fn(&str) -> Ident,
+    collisions: &mut Vec<String>,
+) {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for name in names {
+        let ident = to_ident(name).to_string();
+        match seen.get(&ident) {
+            // The same name twice is a duplicate, which the validation reports
+            Some(previous) if *previous != name => collisions.push(format!(
+                "{}: {} and {} are both generated as `{}`",
+                scope, previous, name, ident
+            )),
+            Some(_) => {}
+            None => {
+                seen.insert(ident, name);
+            }
+        }
+    }
+}
+
+/// Checks the registers and clusters of a register block and everything in them.
+fn check_block(
+    scope: &str,
+    registers: &[Register],
+    clusters: &[Cluster],
+    collisions: &mut Vec<String>,
+) {
+    // The registers and clusters share the fields of the register block and the modules
+    let members = registers
+        .iter()
+        .map(|register| register.name.as_str())
+        .chain(clusters.iter().map(|cluster| cluster.name.as_str()));
+    check_scope(scope, members, snake_ident, collisions);
+
+    for register in registers {
+        let register_scope = format!("{}.{}", scope, register.name);
+        let fields = register.fields.as_deref().unwrap_or_default();
+        check_scope(
+            &register_scope,
+            fields.iter().map(|field| field.name.as_str()),
+            snake_ident,
+            collisions,
+        );
+
+        let enums = fields.iter().flat_map(|field| &field.enums);
+        check_scope(
+            &register_scope,
+            enums.clone().map(|enum_def| enum_def.name.as_str()),
+            pascal_ident,
+            collisions,
+        );
+        for enum_def in enums {
+            check_scope(
+                &format!("{}.{}", register_scope, enum_def.name),
+                enum_def.values.iter().map(|value| value.name.as_str()),
+                pascal_ident,
+                collisions,
+            );
+        }
+    }
+
+    for cluster in clusters {
+        check_block(
+            &format!("{}.{}", scope, cluster.name),
+            &cluster.registers,
+            cluster.clusters.as_deref().unwrap_or_default(),
+            collisions,
+        );
+    }
+}
+
+/// Finds the different names of the description that would be generated as the same
+/// identifier in the same scope, which would not compile.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// A description of every collision.
+pub fn find_collisions(pac: &PeripheralAccess) -> Vec<String> {
+    let mut collisions = Vec::new();
+
+    check_scope(
+        &pac.name,
+        pac.peripherals
+            .iter()
+            .map(|peripheral| peripheral.name.as_str()),
+        snake_ident,
+        &mut collisions,
+    );
+
+    let interrupts = collect_interrupts(pac);
+    check_scope(
+        "Interrupt",
+        interrupts.iter().map(|interrupt| interrupt.name.as_str()),
+        pascal_ident,
+        &mut collisions,
+    );
+    // The same interrupt with different numbers would be two variants with the same name
+    let mut numbers: HashMap<&str, u16> = HashMap::new();
+    for interrupt in &interrupts {
+        match numbers.get(interrupt.name.as_str()) {
+            Some(number) => collisions.push(format!(
+                "Interrupt: {} is listed with the numbers {} and {}",
+                interrupt.name, number, interrupt.number
+            )),
+            None => {
+                numbers.insert(&interrupt.name, interrupt.number);
+            }
+        }
+    }
+
+    check_scope(
+        "memory",
+        pac.memory_regions
+            .iter()
+            .flatten()
+            .map(|region| region.name.as_str()),
+        macro_ident,
+        &mut collisions,
+    );
+
+    for peripheral in &pac.peripherals {
+        check_block(
+            &peripheral.name,
+            &peripheral.registers,
+            peripheral.clusters(),
+            &mut collisions,
+        );
+    }
+
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn names_are_converted_to_the_case_of_the_item() {
+        assert_eq!(snake_ident("TxEn").to_string(), "tx_en");
+        assert_eq!(pascal_ident("char_len").to_string(), "CharLen");
+        assert_eq!(macro_ident("OcmRam").to_string(), "OCM_RAM");
+    }
+
+    #[test]
+    fn invalid_characters_separate_words() {
+        assert_eq!(snake_ident("RX-FIFO/level").to_string(), "rx_fifo_level");
+        assert_eq!(pascal_ident("tx.en").to_string(), "TxEn");
+        assert_eq!(ident("").to_string(), "unnamed");
+    }
+
+    #[test]
+    fn leading_digits_get_a_prefix() {
+        assert_eq!(snake_ident("1WIRE").to_string(), "_1_wire");
+        assert_eq!(pascal_ident("8Bits").to_string(), "_8Bits");
+        assert_eq!(module_file_name("1WIRE"), "_1_wire");
+    }
+
+    #[test]
+    fn keywords_become_raw_identifiers() {
+        assert_eq!(snake_ident("type").to_string(), "r#type");
+        assert_eq!(snake_ident("MATCH").to_string(), "r#match");
+        assert_eq!(ident("async").to_string(), "r#async");
+        // Module files are named without the `r#`
+        assert_eq!(module_file_name("type"), "type");
+    }
+
+    #[test]
+    fn keywords_that_cannot_be_raw_get_a_suffix() {
+        assert_eq!(snake_ident("self").to_string(), "self_");
+        assert_eq!(pascal_ident("self").to_string(), "Self_");
+        assert_eq!(snake_ident("SUPER").to_string(), "super_");
+        assert_eq!(snake_ident("crate").to_string(), "crate_");
+        assert_eq!(module_file_name("mod"), "mod_");
+    }
+
+    #[test]
+    fn names_that_are_not_keywords_are_kept() {
+        assert_eq!(snake_ident("types").to_string(), "types");
+        assert_eq!(pascal_ident("Type").to_string(), "Type");
+    }
+
+    /// A description with a UART peripheral whose MODE register has the given fields,
+    /// and the given interrupts.
+    fn description(fields: serde_json::Value, interrupts: serde_json::Value) -> PeripheralAccess {
+        serde_json::from_value(json!({
+            "name": "Test",
+            "register_size": 32,
+            "peripherals": [{
+                "name": "UART",
+                "base_address": "0xE0000000",
+                "interrupts": interrupts,
+                "registers": [{
+                    "name": "MODE",
+                    "access": "read-write",
+                    "description": "Mode",
+                    "offset": "0x0",
+                    "size": 32,
+                    "fields": fields
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn distinct_identifiers_do_not_collide() {
+        let pac = description(
+            json!([
+                { "name": "tx_en", "bit_range": "0", "description": "" },
+                { "name": "rx_en", "bit_range": "1", "description": "" }
+            ]),
+            json!([{ "name": "UART0", "number": 59, "description": "" }]),
+        );
+        assert!(find_collisions(&pac).is_empty());
+    }
+
+    #[test]
+    fn names_with_the_same_identifier_collide() {
+        let pac = description(
+            json!([
+                { "name": "TxEn", "bit_range": "0", "description": "" },
+                { "name": "TX_EN", "bit_range": "1", "description": "" }
+            ]),
+            json!([]),
+        );
+        assert_eq!(
+            find_collisions(&pac),
+            ["UART.MODE: TxEn and TX_EN are both generated as `tx_en`"]
+        );
+    }
+
+    #[test]
+    fn enum_variants_with_the_same_identifier_collide() {
+        let pac = description(
+            json!([{
+                "name": "char_len",
+                "bit_range": "[2:1]",
+                "description": "",
+                "enums": {
+                    "name": "CharacterLength",
+                    "values": [
+                        { "name": "eight_bits", "value": "0", "description": "" },
+                        { "name": "EightBits", "value": "1", "description": "" }
+                    ]
+                }
+            }]),
+            json!([]),
+        );
+        assert_eq!(
+            find_collisions(&pac),
+            ["UART.MODE.CharacterLength: eight_bits and EightBits are both generated as `EightBits`"]
+        );
+    }
+
+    #[test]
+    fn an_interrupt_listed_with_two_numbers_collides() {
+        let pac = description(
+            json!([]),
+            json!([
+                { "name": "UART0", "number": 59, "description": "" },
+                { "name": "UART0", "number": 60, "description": "" }
+            ]),
+        );
+        assert_eq!(
+            find_collisions(&pac),
+            ["Interrupt: UART0 is listed with the numbers 59 and 60"]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! The gen::inline_deps module generates the replacements for the `volatile-register` and
+//! `bit_field` crates that are used when generating a crate without any dependencies.
+//!
+//! The generated items mirror the parts of those crates that the register modules use,
+//! so the register modules are the same whether or not the dependencies are inlined.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The integer types the inline BitField trait is implemented for.
+const BIT_FIELD_TYPES: [&str; 5] = ["u8", "u16", "u32", "u64", "u128"];
+
+/// Generates the inline volatile register wrappers and bit manipulation trait.
+///
+/// # Returns
+/// A TokenStream containing the RO, WO and RW wrappers and the BitField trait with its impls.
+pub fn gen_inline_dependencies() -> TokenStream {
+    let volatile_registers = gen_volatile_registers();
+    let bit_field = gen_bit_field();
+
+    quote! {
+        #volatile_registers
+        #bit_field
+    }
+}
+
+/// Generates the RO, WO and RW volatile register wrappers on top of
+/// `core::ptr::read_volatile` and `core::ptr::write_volatile`.
+fn gen_volatile_registers() -> TokenStream {
+    quote! {
+        /// A read-only register
+        #[repr(transparent)]
+        pub struct RO<T: Copy> {
+            register: core::cell::UnsafeCell<T>,
+        }
+
+        impl<T: Copy> RO<T> {
+            /// Reads the value of the register
+            #[inline(always)]
+            pub fn read(&self) -> T {
+                unsafe { core::ptr::read_volatile(self.register.get()) }
+            }
+        }
+
+        /// A write-only register
+        #[repr(transparent)]
+        pub struct WO<T: Copy> {
+            register: core::cell::UnsafeCell<T>,
+        }
+
+        impl<T: Copy> WO<T> {
+            /// Writes a value into the register
+            ///
+            /// # Safety
+            /// Writing to a register can have arbitrary side effects on the hardware.
+            #[inline(always)]
+            pub unsafe fn write(&self, value: T) {
+                core::ptr::write_volatile(self.register.get(), value)
+            }
+        }
+
+        /// A read-write register
+        #[repr(transparent)]
+        pub struct RW<T: Copy> {
+            register: core::cell::UnsafeCell<T>,
+        }
+
+        impl<T: Copy> RW<T> {
+            /// Reads the value of the register
+            #[inline(always)]
+            pub fn read(&self) -> T {
+                unsafe { core::ptr::read_volatile(self.register.get()) }
+            }
+
+            /// Writes a value into the register
+            ///
+            /// # Safety
+            /// Writing to a register can have arbitrary side effects on the hardware.
+            #[inline(always)]
+            pub unsafe fn write(&self, value: T) {
+                core::ptr::write_volatile(self.register.get(), value)
+            }
+
+            /// Performs a read-modify-write on the register
+            ///
+            /// # Safety
+            /// Writing to a register can have arbitrary side effects on the hardware.
+            #[inline(always)]
+            pub unsafe fn modify<F>(&self, f: F)
+            where
+                F: FnOnce(T) -> T,
+            {
+                self.write(f(self.read()));
+            }
+        }
+    }
+}
+
+/// Generates the BitField trait and its implementations for the unsigned integer types.
+fn gen_bit_field() -> TokenStream {
+    let impls = BIT_FIELD_TYPES.iter().map(|type_name| {
+        let int_type: TokenStream = type_name.parse().unwrap();
+        quote! {
+            impl BitField for #int_type {
+                #[inline(always)]
+                fn get_bit(&self, bit: usize) -> bool {
+                    (*self >> bit) & 1 != 0
+                }
+
+                #[inline(always)]
+                fn get_bits(&self, range: core::ops::RangeInclusive<usize>) -> Self {
+                    let (start, end) = range.into_inner();
+                    let width = end - start + 1;
+                    let mask = if width >= Self::BITS as usize { !0 } else { (1 << width) - 1 };
+                    (*self >> start) & mask
+                }
+
+                #[inline(always)]
+                fn set_bit(&mut self, bit: usize, value: bool) -> &mut Self {
+                    if value {
+                        *self |= 1 << bit;
+                    } else {
+                        *self &= !(1 << bit);
+                    }
+                    self
+                }
+
+                #[inline(always)]
+                fn set_bits(&mut self, range: core::ops::RangeInclusive<usize>, value: Self) -> &mut Self {
+                    let (start, end) = range.into_inner();
+                    let width = end - start + 1;
+                    let mask = if width >= Self::BITS as usize { !0 } else { (1 << width) - 1 };
+                    assert!(value & !mask == 0, "value does not fit into bit range");
+                    *self = (*self & !(mask << start)) | (value << start);
+                    self
+                }
+            }
+        }
+    });
+
+    quote! {
+        /// Bit manipulation helpers for the raw register values
+        pub trait BitField {
+            /// Returns whether the given bit is set
+            fn get_bit(&self, bit: usize) -> bool;
+
+            /// Returns the bits in the given range, shifted down to bit 0
+            fn get_bits(&self, range: core::ops::RangeInclusive<usize>) -> Self;
+
+            /// Sets or clears the given bit
+            fn set_bit(&mut self, bit: usize, value: bool) -> &mut Self;
+
+            /// Sets the bits in the given range to the given value
+            ///
+            /// Panics if the value does not fit into the range, like the `bit_field` crate.
+            fn set_bits(&mut self, range: core::ops::RangeInclusive<usize>, value: Self) -> &mut Self;
+        }
+
+        #(#impls)*
+    }
+}
@@ -0,0 +1,212 @@
+//! The gen::interrupts module generates the device interrupt definitions: the `Interrupt`
+//! enum, the `__INTERRUPTS` vector table, the per-peripheral interrupt constants and the
+//! `device.x` linker script used by the vector table.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::ident::{macro_ident, pascal_ident};
+use crate::{
+    config::Config,
+    parse::{Interrupt, Peripheral, PeripheralAccess},
+};
+
+/// Collects the interrupts of every peripheral, sorted by interrupt number. An interrupt
+/// line shared by several peripherals is only listed once, under the name it is first
+/// listed with.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The interrupts of the device.
+pub fn collect_interrupts(pac: &PeripheralAccess) -> Vec<&Interrupt> {
+    let mut interrupts: Vec<&Interrupt> = pac
+        .peripherals
+        .iter()
+        .flat_map(|peripheral| peripheral.interrupts.iter().flatten())
+        .filter(|interrupt| interrupt.shared_with.is_none())
+        .collect();
+    interrupts.sort_by_key(|interrupt| interrupt.number);
+    interrupts
+}
+
+/// Generates the root items for the interrupts of the device.
+///
+/// This includes the `InterruptNumber` trait, the `Interrupt` enum, the `cortex-m`
+/// `InterruptNumber` implementation and the `__INTERRUPTS` vector table (behind the `rt`
+/// feature). Nothing is generated if the device has no interrupts.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the interrupt items.
+pub fn gen_interrupt_items(pac: &PeripheralAccess, config: &Config) -> TokenStream {
+    let interrupts = collect_interrupts(pac);
+    if interrupts.is_empty() {
+        return quote! {};
+    }
+
+    let variants = interrupts.iter().map(|interrupt| {
+        let variant_name = pascal_ident(&interrupt.name);
+        let number = interrupt.number;
+        let description = &interrupt.description;
+        quote! {
+            #[doc = #description]
+            #variant_name = #number,
+        }
+    });
+
+    let number_matchers = interrupts.iter().map(|interrupt| {
+        let variant_name = pascal_ident(&interrupt.name);
+        let number = interrupt.number;
+        quote! {
+            #number => Ok(Interrupt::#variant_name),
+        }
+    });
+
+    // The cortex-m crate is an optional dependency, unless the crate has no dependencies at all
+    let cortex_m_impl = if config.zero_dependencies {
+        quote! {}
+    } else {
+        quote! {
+            #[cfg(feature = "cortex-m")]
+            unsafe impl cortex_m::interrupt::InterruptNumber for Interrupt {
+                #[inline(always)]
+                fn number(self) -> u16 {
+                    self as u16
+                }
+            }
+        }
+    };
+
+    let vector_table = gen_vector_table(&interrupts);
+
+    quote! {
+        /// Returns the number of an interrupt
+        pub trait InterruptNumber: Copy {
+            /// Returns the interrupt number
+            fn number(self) -> u16;
+        }
+
+        /// The interrupts of the device
+        #[repr(u16)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Interrupt {
+            #(#variants)*
+        }
+
+        impl InterruptNumber for Interrupt {
+            #[inline(always)]
+            fn number(self) -> u16 {
+                self as u16
+            }
+        }
+
+        impl TryFrom<u16> for Interrupt {
+            type Error = ();
+
+            fn try_from(value: u16) -> Result<Self, Self::Error> {
+                match value {
+                    #(#number_matchers)*
+                    _ => Err(()),
+                }
+            }
+        }
+
+        #cortex_m_impl
+
+        #vector_table
+    }
+}
+
+/// Generates the `__INTERRUPTS` vector table, with a reserved entry for every unused number.
+/// Every handler is an external symbol that the `device.x` linker script defaults to `DefaultHandler`.
+fn gen_vector_table(interrupts: &[&Interrupt]) -> TokenStream {
+    let handler_names: Vec<_> = interrupts
+        .iter()
+        .map(|interrupt| macro_ident(&interrupt.name))
+        .collect();
+
+    let table_size = interrupts
+        .last()
+        .map_or(0, |interrupt| interrupt.number as usize + 1);
+    let vectors = (0..table_size).map(|number| {
+        match interrupts
+            .iter()
+            .position(|interrupt| interrupt.number as usize == number)
+        {
+            Some(index) => {
+                let handler_name = &handler_names[index];
+                quote! { Vector { _handler: #handler_name }, }
+            }
+            None => quote! { Vector { _reserved: 0 }, },
+        }
+    });
+
+    quote! {
+        #[cfg(feature = "rt")]
+        extern "C" {
+            #(fn #handler_names();)*
+        }
+
+        #[doc(hidden)]
+        pub union Vector {
+            _handler: unsafe extern "C" fn(),
+            _reserved: u32,
+        }
+
+        #[cfg(feature = "rt")]
+        #[doc(hidden)]
+        #[link_section = ".vector_table.interrupts"]
+        #[no_mangle]
+        pub static __INTERRUPTS: [Vector; #table_size] = [
+            #(#vectors)*
+        ];
+    }
+}
+
+/// Generates a constant for every interrupt of a peripheral, to be placed in the peripheral module.
+/// The constant of a shared interrupt line is the variant of the interrupt listed first.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral to generate the constants for.
+///
+/// # Returns
+/// A TokenStream representing the interrupt constants.
+pub fn gen_peripheral_interrupts(peripheral: &Peripheral) -> TokenStream {
+    let constants = peripheral.interrupts.iter().flatten().map(|interrupt| {
+        let constant_name = macro_ident(&interrupt.name);
+        let variant_name = pascal_ident(interrupt.shared_with.as_ref().unwrap_or(&interrupt.name));
+        let description = &interrupt.description;
+        quote! {
+            #[doc = #description]
+            pub const #constant_name: Interrupt = Interrupt::#variant_name;
+        }
+    });
+
+    quote! {
+        #(#constants)*
+    }
+}
+
+/// Generates the `device.x` linker script that defaults every interrupt handler to `DefaultHandler`.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The contents of the linker script.
+pub fn gen_device_x(pac: &PeripheralAccess) -> String {
+    collect_interrupts(pac)
+        .iter()
+        .map(|interrupt| {
+            format!(
+                "PROVIDE({} = DefaultHandler);\n",
+                macro_ident(&interrupt.name)
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,203 @@
+//! The gen::linux module generates the Linux userspace backend of the peripheral access
+//! crate, enabled by its `linux` feature. Under Linux the physical addresses of the
+//! description are meaningless, so the registers are reached through a memory mapping of
+//! `/dev/mem`, of a UIO device (`/dev/uioN`) or of any other file, which yields the same
+//! typed RegisterBlock as the bare metal constructors:
+//!
+//! ```ignore
+//! let mut uart = uart::RegisterBlock::map_dev_mem()?;
+//! uart.mode.modify(|mode| mode.with_char_len(CharacterLength::SixBits));
+//! ```
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{config::Config, parse::Peripheral};
+
+/// Generates the `linux` module with the Mapped type that owns a mapping and derefs to
+/// the register block in it.
+///
+/// # Arguments
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the module, or nothing for a crate without dependencies.
+pub fn gen_linux_module(config: &Config) -> TokenStream {
+    // The mapping needs the libc crate
+    if config.zero_dependencies {
+        return quote! {};
+    }
+
+    quote! {
+        /// Access to the registers from Linux userspace, through a memory mapping of
+        /// `/dev/mem`, of a UIO device or of any other file
+        #[cfg(feature = "linux")]
+        pub mod linux {
+            extern crate std;
+
+            use core::{
+                ops::{Deref, DerefMut},
+                ptr::NonNull,
+            };
+            use std::{
+                fs::{self, OpenOptions},
+                os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+            };
+            pub use std::{io, path::Path};
+
+            /// A register block mapped into the address space of the process.
+            /// It derefs to the register block and is unmapped when dropped.
+            pub struct Mapped<T> {
+                mapping: *mut libc::c_void,
+                length: usize,
+                block: NonNull<T>,
+            }
+
+            impl<T> Mapped<T> {
+                /// Maps the register block found at the given offset of a file, such as
+                /// `/dev/mem` at the physical address of a peripheral
+                ///
+                /// # Safety
+                /// The file must hold a register block of type T at the offset.
+                pub unsafe fn new(path: impl AsRef<Path>, offset: u64) -> io::Result<Self> {
+                    let page_offset = offset % page_size() as u64;
+                    Self::map(path.as_ref(), offset - page_offset, page_offset as usize)
+                }
+
+                /// Maps the register block found in a map of a UIO device, such as the
+                /// map 0 of `/dev/uio0`. The position of the block in the first page of the
+                /// map is read from sysfs.
+                ///
+                /// # Safety
+                /// The map must hold a register block of type T.
+                pub unsafe fn uio(path: impl AsRef<Path>, map: usize) -> io::Result<Self> {
+                    let path = path.as_ref();
+                    // UIO selects the map to mmap with the offset, in pages
+                    let block_offset = path
+                        .file_name()
+                        .map(|name| {
+                            Path::new("/sys/class/uio")
+                                .join(name)
+                                .join(std::format!("maps/map{}/offset", map))
+                        })
+                        .and_then(|offset_file| fs::read_to_string(offset_file).ok())
+                        .and_then(|offset| {
+                            usize::from_str_radix(offset.trim().trim_start_matches("0x"), 16).ok()
+                        })
+                        .unwrap_or(0);
+                    Self::map(path, (map * page_size()) as u64, block_offset)
+                }
+
+                /// Maps the pages of the file that start at map_offset and contain the
+                /// register block at block_offset from there.
+                unsafe fn map(path: &Path, map_offset: u64, block_offset: usize) -> io::Result<Self> {
+                    let file = OpenOptions::new()
+                        .read(true)
+                        .write(true)
+                        .custom_flags(libc::O_SYNC)
+                        .open(path)?;
+                    let length = block_offset + core::mem::size_of::<T>();
+
+                    // Touching a mapping past the end of a regular file raises SIGBUS
+                    let metadata = file.metadata()?;
+                    if metadata.is_file() && metadata.len() < map_offset + length as u64 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "the file is smaller than the register block",
+                        ));
+                    }
+
+                    let mapping = libc::mmap(
+                        core::ptr::null_mut(),
+                        length,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_SHARED,
+                        file.as_raw_fd(),
+                        map_offset as libc::off_t,
+                    );
+                    if mapping == libc::MAP_FAILED {
+                        return Err(io::Error::last_os_error());
+                    }
+
+                    Ok(Mapped {
+                        mapping,
+                        length,
+                        block: NonNull::new_unchecked(mapping.cast::<u8>().add(block_offset).cast()),
+                    })
+                }
+            }
+
+            impl<T> Deref for Mapped<T> {
+                type Target = T;
+
+                fn deref(&self) -> &T {
+                    unsafe { self.block.as_ref() }
+                }
+            }
+
+            impl<T> DerefMut for Mapped<T> {
+                fn deref_mut(&mut self) -> &mut T {
+                    unsafe { self.block.as_mut() }
+                }
+            }
+
+            impl<T> Drop for Mapped<T> {
+                fn drop(&mut self) {
+                    unsafe {
+                        libc::munmap(self.mapping, self.length);
+                    }
+                }
+            }
+
+            /// Returns the size of a memory page
+            fn page_size() -> usize {
+                unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+            }
+        }
+    }
+}
+
+/// Generates the constructors that map the RegisterBlock of a peripheral under Linux.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral to generate the constructors for.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the impl block, or nothing for a crate without dependencies.
+pub fn gen_mapping_constructors(peripheral: &Peripheral, config: &Config) -> TokenStream {
+    if config.zero_dependencies {
+        return quote! {};
+    }
+
+    let base_address = peripheral.base_address as u64;
+
+    quote! {
+        #[cfg(feature = "linux")]
+        impl RegisterBlock {
+            /// Maps the registers through `/dev/mem`, at the physical address of the
+            /// peripheral. This needs root privileges.
+            pub fn map_dev_mem() -> linux::io::Result<linux::Mapped<Self>> {
+                unsafe { linux::Mapped::new("/dev/mem", #base_address) }
+            }
+
+            /// Maps the registers through a UIO device, such as `/dev/uio0`, whose map
+            /// of the given index covers the peripheral.
+            pub fn map_uio(
+                path: impl AsRef<linux::Path>,
+                map: usize,
+            ) -> linux::io::Result<linux::Mapped<Self>> {
+                unsafe { linux::Mapped::uio(path, map) }
+            }
+
+            /// Maps the registers found at the given offset of a file, such as a regular
+            /// file standing in for the device in tests.
+            pub fn map_file(
+                path: impl AsRef<linux::Path>,
+                offset: u64,
+            ) -> linux::io::Result<linux::Mapped<Self>> {
+                unsafe { linux::Mapped::new(path, offset) }
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! The gen::manifest module keeps track of the files generated into a crate, so the crate
+//! can be regenerated without clobbering hand-written changes.
+//!
+//! The manifest is a text file in the root of the generated crate with one
+//! `<hash> <path>` line per generated file, where the hash is taken from the contents
+//! that were written. On the next generation, a file whose hash on disk no longer matches
+//! was edited by hand, and files that are no longer generated are orphans to be deleted.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+
+use super::{utils, GeneratedFile};
+use crate::error::Error;
+
+/// The name of the manifest file in the root of the generated crate.
+pub const MANIFEST_FILE_NAME: &str = ".rust-pac-gen-manifest";
+
+/// The OutputManifest struct maps every generated file (relative to the crate root)
+/// to the hash of the contents that were written.
+#[derive(Debug, Default)]
+pub struct OutputManifest {
+    pub files: BTreeMap<PathBuf, u64>,
+}
+
+impl OutputManifest {
+    /// Loads the manifest from the output directory.
+    /// A missing manifest is treated as an empty one.
+    ///
+    /// # Arguments
+    /// out_dir: &Path - The root of the generated crate.
+    ///
+    /// # Returns
+    /// A Result containing the loaded manifest.
+    pub fn load(out_dir: &Path) -> Result<Self, Error> {
+        let path = out_dir.join(MANIFEST_FILE_NAME);
+        if !path.exists() {
+            return Ok(OutputManifest::default());
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let files = content
+            .lines()
+            .filter_map(|line| {
+                let (hash, file) = line.split_once(' ')?;
+                let hash = u64::from_str_radix(hash, 16).ok()?;
+                Some((PathBuf::from(file), hash))
+            })
+            .collect();
+
+        Ok(OutputManifest { files })
+    }
+
+    /// Saves the manifest to the output directory.
+    ///
+    /// # Arguments
+    /// out_dir: &Path - The root of the generated crate.
+    ///
+    /// # Returns
+    /// A Result containing the success or error of the write operation.
+    pub fn save(&self, out_dir: &Path) -> Result<(), Error> {
+        let path = out_dir.join(MANIFEST_FILE_NAME);
+        let content: String = self
+            .files
+            .iter()
+            .map(|(file, hash)| format!("{:016x} {}\n", hash, file.to_string_lossy()))
+            .collect();
+        utils::write_to_file_str(&path, &content).map_err(|e| Error::io(&path, e))
+    }
+}
+
+/// Writes the generated files to the output directory while keeping track of them in
+/// the manifest.
+///
+/// - Files that were edited by hand since they were generated are not overwritten.
+/// - Files that exist but were never generated are not overwritten either.
+/// - Files from the previous generation that are no longer generated are deleted.
+/// - User files are only written if they do not exist yet.
+///
+/// If any file would be clobbered, nothing is written and an error listing the files is
+/// returned, unless `force` is set.
+///
+/// # Arguments
+/// out_dir: &Path - The root of the generated crate.
+/// files: &[GeneratedFile] - The generated files to write.
+/// force: bool - Overwrite and delete files even if they were edited by hand.
+///
+/// # Returns
+/// A Result containing the success or error of the write operations.
+pub fn write_tracked_files(
+    out_dir: &Path,
+    files: &[GeneratedFile],
+    force: bool,
+) -> Result<(), Error> {
+    let previous = OutputManifest::load(out_dir)?;
+    let mut manifest = OutputManifest::default();
+    let mut modified = Vec::new();
+
+    let tracked_files: Vec<&GeneratedFile> = files.iter().filter(|f| !f.user_file).collect();
+
+    // Check the files that are about to be written
+    for file in &tracked_files {
+        let on_disk = read_if_exists(&out_dir.join(&file.path))?;
+        let is_modified = match (on_disk, previous.files.get(&file.path)) {
+            (None, _) => false,
+            (Some(contents), Some(hash)) => utils::hash_contents(&contents) != *hash,
+            (Some(contents), None) => contents != file.contents.as_bytes(),
+        };
+        if is_modified {
+            modified.push(file.path.clone());
+        }
+    }
+
+    // Check the files that are no longer generated
+    let orphans: Vec<&PathBuf> = previous
+        .files
+        .keys()
+        .filter(|path| !tracked_files.iter().any(|f| &f.path == *path))
+        .collect();
+    for orphan in &orphans {
+        if let Some(contents) = read_if_exists(&out_dir.join(orphan))? {
+            if utils::hash_contents(&contents) != previous.files[*orphan] {
+                modified.push((*orphan).clone());
+            }
+        }
+    }
+
+    if !modified.is_empty() && !force {
+        return Err(Error::ModifiedFiles(modified));
+    }
+
+    for file in files {
+        let path = out_dir.join(&file.path);
+        if file.user_file {
+            if !path.exists() {
+                info!("Creating user file {:?}", path);
+                write_file(&path, &file.contents)?;
+            }
+            continue;
+        }
+
+        // Unchanged files are left alone to keep their modification time
+        if read_if_exists(&path)?.as_deref() != Some(file.contents.as_bytes()) {
+            write_file(&path, &file.contents)?;
+        }
+        manifest.files.insert(
+            file.path.clone(),
+            utils::hash_contents(file.contents.as_bytes()),
+        );
+    }
+
+    for orphan in orphans {
+        let path = out_dir.join(orphan);
+        if path.exists() {
+            info!("Removing orphaned file {:?}", path);
+            std::fs::remove_file(&path).map_err(|e| Error::io(&path, e))?;
+            remove_empty_parents(out_dir, &path);
+        }
+    }
+
+    manifest.save(out_dir)
+}
+
+/// Reads a file, returning None if it does not exist.
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>, Error> {
+    match std::fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io(path, e)),
+    }
+}
+
+/// Writes a file, creating its parent folders.
+fn write_file(path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        utils::create_folder(parent).map_err(|e| Error::io(parent, e))?;
+    }
+    debug!("Writing {:?}", path);
+    utils::write_to_file_str(path, contents).map_err(|e| Error::io(path, e))
+}
+
+/// Removes the empty folders left behind by a deleted file, up to the output directory.
+fn remove_empty_parents(out_dir: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == out_dir || std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
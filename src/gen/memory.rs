@@ -0,0 +1,102 @@
+//! The gen::memory module generates the memory map of the device from the memory
+//! region descriptions: the `memory.x` linker script and the `memory` module with
+//! a constant per region, so the crate and the linker agree on the address map.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::ident::macro_ident;
+use crate::parse::PeripheralAccess;
+
+/// Returns whether the description declares any memory regions.
+pub fn has_memory_regions(pac: &PeripheralAccess) -> bool {
+    pac.memory_regions
+        .as_ref()
+        .is_some_and(|regions| !regions.is_empty())
+}
+
+/// Generates the `memory.x` linker script with a MEMORY command listing every region.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The contents of the linker script.
+pub fn gen_memory_x(pac: &PeripheralAccess) -> String {
+    let regions: String = pac
+        .memory_regions
+        .iter()
+        .flatten()
+        .map(|region| {
+            let attributes = region
+                .attributes
+                .as_ref()
+                .map(|attributes| format!(" ({})", attributes))
+                .unwrap_or_default();
+            format!(
+                "  {}{} : ORIGIN = 0x{:08X}, LENGTH = 0x{:X}\n",
+                region.name, attributes, region.origin, region.length
+            )
+        })
+        .collect();
+
+    format!("MEMORY\n{{\n{}}}\n", regions)
+}
+
+/// Generates the `memory` module with the MemoryRegion type and a constant per region.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// A TokenStream representing the module, or nothing if there are no memory regions.
+pub fn gen_memory_items(pac: &PeripheralAccess) -> TokenStream {
+    if !has_memory_regions(pac) {
+        return quote! {};
+    }
+
+    let constants = pac.memory_regions.iter().flatten().map(|region| {
+        let constant_name = macro_ident(&region.name);
+        let origin = region.origin;
+        let length = region.length;
+        let doc = region
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("The {} memory region", region.name));
+        quote! {
+            #[doc = #doc]
+            pub const #constant_name: MemoryRegion = MemoryRegion {
+                origin: #origin,
+                length: #length,
+            };
+        }
+    });
+
+    quote! {
+        /// The memory regions of the device, matching the generated memory.x
+        pub mod memory {
+            /// A region of the address map
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct MemoryRegion {
+                /// The first address of the region
+                pub origin: usize,
+                /// The size of the region in bytes
+                pub length: usize,
+            }
+
+            impl MemoryRegion {
+                /// Returns the address right after the end of the region
+                pub const fn end(&self) -> usize {
+                    self.origin + self.length
+                }
+
+                /// Returns whether the address lies in the region
+                pub const fn contains(&self, address: usize) -> bool {
+                    address >= self.origin && address - self.origin < self.length
+                }
+            }
+
+            #(#constants)*
+        }
+    }
+}
@@ -1,92 +1,258 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use atomic::AtomicAccess;
+use cache::ModuleCache;
+use ident::{module_file_name, pascal_ident, snake_ident};
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
-use stringcase::Caser;
+use quote::quote;
 use syn::Ident;
 use utils::{
-    calculate_reserved_region, create_folder, format_token_stream, get_smallest_rust_type,
-    usize_to_bool, write_to_file_str,
+    bit_range_mask, format_token_stream, get_smallest_rust_type, usize_to_bool, write_to_file_str,
 };
 
 use crate::{
-    config::Config,
+    config::{Config, Naming},
+    error::Error,
     parse::{
         utils::{Access, BitRange},
-        Enum, Peripheral, PeripheralAccess, Register,
+        Cluster, Enum, Field, Peripheral, PeripheralAccess, Register,
     },
 };
 
+pub mod atomic;
+pub mod build_rs;
+pub mod cache;
+pub mod cargo_toml;
+pub mod debug;
+pub mod diagram;
+pub mod docs;
+pub mod gdb;
+pub mod ident;
+pub mod inline_deps;
+pub mod interrupts;
+pub mod linux;
+pub mod manifest;
+pub mod memory;
+pub mod naming;
+pub mod openocd;
+pub mod register_block;
+pub mod serde_impls;
+pub mod svd2rust;
 pub mod utils;
 
-/// Generates the peripheral access crate code using syn and quote.
+pub use cargo_toml::gen_cargo_toml;
+
+/// The GeneratedFile struct represents a single file of the generated crate.
+///
+/// The path is relative to the root of the generated crate (e.g. `src/uart/mod.rs`).
+#[derive(Debug, Clone)]
+pub struct GeneratedFile {
+    pub path: PathBuf,
+    pub contents: String,
+    /// A file that belongs to the user, such as a peripheral extension file.
+    /// It is only written when it does not exist yet and is never tracked, overwritten or deleted.
+    pub user_file: bool,
+}
+
+impl GeneratedFile {
+    /// Creates a generated file that is owned by the generator.
+    pub fn new(path: impl Into<PathBuf>, contents: String) -> Self {
+        GeneratedFile {
+            path: path.into(),
+            contents,
+            user_file: false,
+        }
+    }
+
+    /// Creates a file that is handed over to the user after it is first written.
+    pub fn user_file(path: impl Into<PathBuf>, contents: String) -> Self {
+        GeneratedFile {
+            path: path.into(),
+            contents,
+            user_file: true,
+        }
+    }
+}
+
+/// Generates the peripheral access crate code using syn and quote and writes it to disk.
+///
+/// Files edited by hand since the last generation are never overwritten; see
+/// `manifest::write_tracked_files`.
 ///
 /// # Arguments
-/// out_dir: &PathBuf - The output directory where the crate will be generated to.
+/// out_dir: &Path - The output directory where the crate will be generated to.
+/// crate_name: &str - The name of the generated crate.
 /// pac: &PeripheralAccess - The parsed peripheral access data structure.
 /// config_file: &Config - The configuration file that contains the versions of the dependencies.
+///
+/// # Returns
+/// A Result containing the list of files that were written.
 pub fn generate_pac_code(
-    out_dir: &PathBuf,
+    out_dir: &Path,
     crate_name: &str,
     pac: &PeripheralAccess,
     config_file: &Config,
-) {
-    // First, make sure the output directory exists. If it doesn't, create it.
-    if !out_dir.exists() {
-        std::fs::create_dir_all(out_dir).unwrap();
+) -> Result<Vec<GeneratedFile>, Error> {
+    let files = generate_pac_files(crate_name, pac, config_file);
+    manifest::write_tracked_files(out_dir, &files, false)?;
+    Ok(files)
+}
+
+/// Generates the peripheral access crate code in memory.
+///
+/// # Arguments
+/// crate_name: &str - The name of the generated crate.
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config_file: &Config - The configuration file that contains the versions of the dependencies.
+///
+/// # Returns
+/// The list of generated files, with paths relative to the root of the crate.
+pub fn generate_pac_files(
+    crate_name: &str,
+    pac: &PeripheralAccess,
+    config_file: &Config,
+) -> Vec<GeneratedFile> {
+    generate_pac_files_cached(crate_name, pac, config_file, &mut ModuleCache::default())
+}
+
+/// Generates the peripheral access crate code in memory, taking the modules of the
+/// peripherals whose definitions did not change since the last run from the cache.
+///
+/// # Arguments
+/// crate_name: &str - The name of the generated crate.
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config_file: &Config - The configuration file that contains the versions of the dependencies.
+/// cache: &mut ModuleCache - The generated modules of the previous run.
+///
+/// # Returns
+/// The list of generated files, with paths relative to the root of the crate.
+pub fn generate_pac_files_cached(
+    crate_name: &str,
+    pac: &PeripheralAccess,
+    config_file: &Config,
+    cache: &mut ModuleCache,
+) -> Vec<GeneratedFile> {
+    let mut files = Vec::new();
+    cache.start_run(pac);
+
+    // Create the Cargo.toml file in the root of the crate
+    files.push(GeneratedFile::new(
+        "Cargo.toml",
+        gen_cargo_toml(crate_name, pac, config_file),
+    ));
+
+    // The vector table needs a linker script with the default handlers
+    if !interrupts::collect_interrupts(pac).is_empty() {
+        files.push(GeneratedFile::new(
+            "device.x",
+            interrupts::gen_device_x(pac),
+        ));
     }
 
-    // Next, create a src directory inside the output directory.
-    let src_dir = out_dir.join("src");
-    if !src_dir.exists() {
-        std::fs::create_dir_all(&src_dir).unwrap();
+    if memory::has_memory_regions(pac) {
+        files.push(GeneratedFile::new("memory.x", memory::gen_memory_x(pac)));
     }
 
-    // Create the Cargo.toml file in the output directory
-    let cargo_toml = gen_cargo_toml(crate_name, config_file);
-    let cargo_toml_path = out_dir.join("Cargo.toml");
-    write_to_file_str(&cargo_toml_path, &cargo_toml).unwrap();
+    // The build script puts the linker scripts on the linker search path
+    if let Some(build_rs) = build_rs::gen_build_rs(pac) {
+        files.push(GeneratedFile::new(
+            "build.rs",
+            format_token_stream(&build_rs),
+        ));
+    }
 
     // Generate the lib.rs file
-    let lib_code = gen_lib_code(pac, pac.is_no_std);
-    let lib_code_formatted = format_token_stream(&lib_code);
-    let lib_rs_path = src_dir.join("lib.rs");
-    write_to_file_str(&lib_rs_path, &lib_code_formatted).unwrap();
+    let lib_code = gen_lib_code(pac, pac.is_no_std, config_file);
+    files.push(GeneratedFile::new(
+        Path::new("src").join("lib.rs"),
+        format_token_stream(&lib_code),
+    ));
 
     // Generate the peripheral modules
     for peripheral in &pac.peripherals {
-        gen_peripherals(peripheral, &src_dir);
+        let fingerprint = ModuleCache::fingerprint(pac, peripheral, config_file);
+        files.extend(cache.get_or_generate(&peripheral.name, fingerprint, || {
+            let atomic = AtomicAccess::new(pac, peripheral, config_file);
+            gen_peripherals(peripheral, Path::new("src"), config_file, &atomic)
+        }));
+    }
+
+    // The debugger scripts are not part of the Rust code, so they live outside of src
+    if config_file.debug_scripts {
+        files.push(GeneratedFile::new(
+            Path::new("debug").join(format!("{}.tcl", crate_name)),
+            openocd::gen_openocd_script(pac),
+        ));
+        files.push(GeneratedFile::new(
+            Path::new("debug").join(format!("{}-gdb.py", crate_name)),
+            gdb::gen_gdb_script(pac),
+        ));
     }
+
+    files
 }
 
-/// Generates a Cargo.toml file for the peripheral access crate.
-/// Since the Cargo.toml file is not a Rust source file, we will
-/// use a String to represent the contents of the file.
+/// Generates the peripheral access crate code as a single self-contained Rust file.
+///
+/// The peripheral and register modules are inlined, and the items that normally live
+/// at the crate root are placed in a `traits` module that is re-exported, so the file
+/// can be vendored into an existing crate or pulled in with `include!`.
 ///
 /// # Arguments
-/// crate_name: &str - The name of the crate.
-/// config: &Config - The configuration file that contains the versions of the dependencies.
+/// crate_name: &str - The name of the crate, used as the file name.
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config_file: &Config - The configuration file that contains the generation options.
 ///
 /// # Returns
-/// The contents of the generated Cargo.toml file.
-pub fn gen_cargo_toml(crate_name: &str, config: &Config) -> String {
-    format!(
-        r#"[package]
-name = "{crate_name}"
-version = "0.1.0"
-edition = "2021"
-
-[dependencies]
-bit_field = "{bit_field_version}"
-volatile-register = "{volatile_register_version}"
-        "#,
-        crate_name = crate_name,
-        bit_field_version = config.bit_field_version,
-        volatile_register_version = config.volatile_register_version
-    )
+/// The generated file, with a path of `<crate_name>.rs`.
+pub fn generate_single_file(
+    crate_name: &str,
+    pac: &PeripheralAccess,
+    config_file: &Config,
+) -> GeneratedFile {
+    let code = gen_single_file_code(pac, config_file);
+    GeneratedFile::new(format!("{}.rs", crate_name), format_token_stream(&code))
+}
+
+/// Writes the generated files to the output directory, creating any missing folders.
+///
+/// This does not track the files; see `manifest::write_tracked_files` for regenerating
+/// a crate without clobbering hand-written changes.
+///
+/// # Arguments
+/// out_dir: &Path - The output directory where the crate will be generated to.
+/// files: &[GeneratedFile] - The generated files to write.
+///
+/// # Returns
+/// A Result containing the success or error of the write operations.
+pub fn write_generated_files(out_dir: &Path, files: &[GeneratedFile]) -> Result<(), Error> {
+    for file in files {
+        let path = out_dir.join(&file.path);
+        if file.user_file && path.exists() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            utils::create_folder(parent).map_err(|e| Error::io(parent, e))?;
+        }
+        write_to_file_str(&path, &file.contents).map_err(|e| Error::io(&path, e))?;
+    }
+    Ok(())
 }
 
-pub fn gen_lib_code(pac: &PeripheralAccess, is_no_std: Option<bool>) -> TokenStream {
+/// Generates the lib.rs file of the peripheral access crate.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// is_no_std: Option<bool> - Whether the crate is no_std. Defaults to true.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the lib.rs file.
+pub fn gen_lib_code(
+    pac: &PeripheralAccess,
+    is_no_std: Option<bool>,
+    config: &Config,
+) -> TokenStream {
     // Create an empty token stream to hold the generated code
     let mut tokens = TokenStream::new();
 
@@ -101,25 +267,118 @@ pub fn gen_lib_code(pac: &PeripheralAccess, is_no_std: Option<bool>) -> TokenStr
 
     tokens.extend(no_std_attr);
 
-    // Add the volatile_register and bit_field imports
-    let imports = quote! {
-        pub use bit_field::BitField;
-        pub use volatile_register::{RO, WO, RW};
-    };
+    // Get the list of peripherals from the parsed data to generate module declarations for each peripheral
+    let peripheral_mod_decls = pac
+        .peripherals
+        .iter()
+        .map(|peripheral| {
+            let peripheral_name_ident = snake_ident(&peripheral.name);
+            quote! {
+                pub mod #peripheral_name_ident;
+            }
+        })
+        .collect();
 
-    tokens.extend(imports);
+    tokens.extend(gen_root_items(pac, peripheral_mod_decls, config));
 
-    // Get the list of peripherals from the parsed data to generate module declarations for each peripheral
-    for peripheral in &pac.peripherals {
-        let peripheral_name = &peripheral.name;
-        let peripheral_name_ident = format_ident!("{}", peripheral_name.to_snake_case().trim());
+    tokens
+}
 
-        let peripheral_mod_decl = quote! {
-            pub mod #peripheral_name_ident;
-        };
+/// Generates the whole peripheral access crate as a single TokenStream, with every
+/// peripheral and register module inlined instead of declared as a separate file.
+///
+/// The crate level attributes (such as `#![no_std]`) are left out, so the result can
+/// be expanded anywhere items are allowed. The items are wrapped in a hidden module that
+/// is glob re-exported, so the `unexpected_cfgs` lint can be allowed for the feature gates
+/// the including crate does not declare.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream containing the root items and the inline peripheral modules.
+pub fn gen_pac_tokens(pac: &PeripheralAccess, config: &Config) -> TokenStream {
+    let peripheral_mods = pac
+        .peripherals
+        .iter()
+        .map(|peripheral| {
+            let atomic = AtomicAccess::new(pac, peripheral, config);
+            gen_inline_peripheral_module(peripheral, config, &atomic)
+        })
+        .collect();
+
+    let root_items = gen_root_items(pac, peripheral_mods, config);
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(unexpected_cfgs)]
+        pub mod __pac {
+            #root_items
+        }
+
+        pub use __pac::*;
+    }
+}
+
+/// Generates the code for the single file output mode.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream containing the `traits` module and the inline peripheral modules.
+pub fn gen_single_file_code(pac: &PeripheralAccess, config: &Config) -> TokenStream {
+    let root_items = gen_root_items(pac, Vec::new(), config);
+    let peripheral_mods = pac.peripherals.iter().map(|peripheral| {
+        let atomic = AtomicAccess::new(pac, peripheral, config);
+        gen_inline_peripheral_module(peripheral, config, &atomic)
+    });
 
-        tokens.extend(peripheral_mod_decl);
+    quote! {
+        #[allow(unexpected_cfgs)]
+        pub mod traits {
+            #root_items
+        }
+
+        pub use traits::*;
+
+        #(#peripheral_mods)*
     }
+}
+
+/// Generates the items at the root of the peripheral access crate: the re-exported
+/// dependencies, the given peripheral modules, the register traits, the interrupts and
+/// the memory regions.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// peripheral_mods: Vec<TokenStream> - The peripheral module declarations or inline modules.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the root items.
+fn gen_root_items(
+    pac: &PeripheralAccess,
+    peripheral_mods: Vec<TokenStream>,
+    config: &Config,
+) -> TokenStream {
+    let mut tokens = TokenStream::new();
+
+    // Add the volatile_register and bit_field imports, or their inline replacements
+    let imports = if config.zero_dependencies {
+        inline_deps::gen_inline_dependencies()
+    } else {
+        quote! {
+            pub use bit_field::BitField;
+            pub use volatile_register::{RO, WO, RW};
+        }
+    };
+
+    tokens.extend(imports);
+
+    tokens.extend(peripheral_mods);
 
     // Next generate the traits for the peripheral access crate
     let trait_tokens = quote! {
@@ -172,6 +431,20 @@ pub fn gen_lib_code(pac: &PeripheralAccess, is_no_std: Option<bool>) -> TokenStr
 
     tokens.extend(trait_tokens);
 
+    if config.svd2rust_api {
+        tokens.extend(svd2rust::gen_proxy_types());
+    }
+
+    if !config.zero_dependencies {
+        tokens.extend(serde_impls::gen_serde_items());
+    }
+
+    tokens.extend(interrupts::gen_interrupt_items(pac, config));
+
+    tokens.extend(memory::gen_memory_items(pac));
+
+    tokens.extend(linux::gen_linux_module(config));
+
     tokens
 }
 
@@ -180,125 +453,318 @@ pub fn gen_lib_code(pac: &PeripheralAccess, is_no_std: Option<bool>) -> TokenStr
 ///
 /// # Arguments
 /// peripheral: &Peripheral - The peripheral to generate code for.
-/// src_dir: &PathBuf - The path to the src directory where the code will be generated.
-pub fn gen_peripherals(peripheral: &Peripheral, src_dir: &PathBuf) {
-    // First, create a folder for the peripheral module
-    let peripheral_name = &peripheral.name;
-    let peripheral_name_snake = peripheral_name.to_snake_case();
-    create_folder(&src_dir.join(&peripheral_name_snake)).unwrap();
-
-    // Create a folder for each register module
-    for register in &peripheral.registers {
-        if register.fields.is_some() {
-            let register_name = &register.name;
-            let register_name_snake = register_name.to_snake_case();
-            create_folder(
-                &src_dir
-                    .join(&peripheral_name_snake)
-                    .join(&register_name_snake),
-            )
-            .unwrap();
-        }
-    }
+/// src_dir: &Path - The path of the src directory, relative to the root of the crate.
+/// config: &Config - The configuration file that contains the generation options.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the peripheral.
+///
+/// # Returns
+/// The generated peripheral and register module files.
+pub fn gen_peripherals(
+    peripheral: &Peripheral,
+    src_dir: &Path,
+    config: &Config,
+    atomic: &AtomicAccess,
+) -> Vec<GeneratedFile> {
+    let peripheral_dir = src_dir.join(module_file_name(&peripheral.name));
+    let mut files = Vec::new();
 
     // Generate the peripheral module
-    let peripheral_mod = gen_peripheral_module(peripheral);
-    let peripheral_mod_formatted = format_token_stream(&peripheral_mod);
-    let peripheral_mod_path = src_dir.join(&peripheral_name_snake).join("mod.rs");
-    write_to_file_str(&peripheral_mod_path, &peripheral_mod_formatted).unwrap();
+    let peripheral_mod = gen_peripheral_module(peripheral, config);
+    files.push(GeneratedFile::new(
+        peripheral_dir.join("mod.rs"),
+        format_token_stream(&peripheral_mod),
+    ));
+
+    // The extension file is only created once, after that it belongs to the user
+    if config.extension_files {
+        files.push(GeneratedFile::user_file(
+            peripheral_dir.join("ext.rs"),
+            gen_extension_file(peripheral),
+        ));
+    }
+
+    // Next, generate the register modules (for those with fields) and the cluster modules
+    files.extend(gen_child_files(
+        &peripheral.registers,
+        peripheral.clusters(),
+        &peripheral_dir,
+        config,
+        atomic,
+    ));
 
-    // Next, generate the register modules (for those with fields)
-    for register in &peripheral.registers {
+    files
+}
+
+/// Generates the files of the child modules of a register block: a module for every
+/// register with fields, and a module for every cluster with its own child modules.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+/// dir: &Path - The directory of the module that contains the register block.
+/// config: &Config - The configuration file that contains the generation options.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the register block.
+///
+/// # Returns
+/// The generated register and cluster module files.
+fn gen_child_files(
+    registers: &[Register],
+    clusters: &[Cluster],
+    dir: &Path,
+    config: &Config,
+    atomic: &AtomicAccess,
+) -> Vec<GeneratedFile> {
+    let mut files = Vec::new();
+
+    for register in registers {
         if register.fields.is_some() {
-            gen_register_module(register, &src_dir.join(&peripheral_name_snake));
+            let register_module = gen_register_module(register, config, atomic);
+            files.push(GeneratedFile::new(
+                dir.join(module_file_name(&register.name)).join("mod.rs"),
+                format_token_stream(&register_module),
+            ));
         }
     }
+
+    for cluster in clusters {
+        let cluster_dir = dir.join(module_file_name(&cluster.name));
+        let child_mods = gen_child_mod_decls(&cluster.registers, cluster.clusters());
+        let cluster_module = register_block::gen_cluster_items(cluster, child_mods, config);
+        files.push(GeneratedFile::new(
+            cluster_dir.join("mod.rs"),
+            format_token_stream(&cluster_module),
+        ));
+        files.extend(gen_child_files(
+            &cluster.registers,
+            cluster.clusters(),
+            &cluster_dir,
+            config,
+            &atomic.in_cluster(cluster),
+        ));
+    }
+
+    files
 }
 
-/// Generates the top level module for a peripheral.
+/// Generates the module declarations for the child modules of a register block.
+/// A module is only generated for a register if it has at least one field.
 ///
 /// # Arguments
-/// peripheral: &Peripheral - The peripheral to generate the module for.
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
 ///
 /// # Returns
-/// A TokenStream representing the generated module.
-pub fn gen_peripheral_module(peripheral: &Peripheral) -> TokenStream {
-    // The top level module includes the RegisterBlock and declarations for the register modules
+/// The `pub mod` declarations of the child modules.
+fn gen_child_mod_decls(registers: &[Register], clusters: &[Cluster]) -> Vec<TokenStream> {
+    let register_names = registers
+        .iter()
+        .filter(|register| register.fields.is_some())
+        .map(|register| &register.name);
+    let cluster_names = clusters.iter().map(|cluster| &cluster.name);
+
+    register_names
+        .chain(cluster_names)
+        .map(|name| {
+            let module_name_ident = snake_ident(name);
+            quote! {
+                pub mod #module_name_ident;
+            }
+        })
+        .collect()
+}
 
-    // First, get the register module declarations
-    // A module is only generated for a register if it has at least one field
-    let register_mod_decls: Vec<TokenStream> = peripheral
-        .registers
+/// Generates the inline child modules of a register block, including the child
+/// modules of every cluster.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+/// config: &Config - The configuration file that contains the generation options.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the register block.
+///
+/// # Returns
+/// The `pub mod` items of the child modules.
+fn gen_inline_child_mods(
+    registers: &[Register],
+    clusters: &[Cluster],
+    config: &Config,
+    atomic: &AtomicAccess,
+) -> Vec<TokenStream> {
+    let register_mods = registers
         .iter()
+        .filter(|register| register.fields.is_some())
         .map(|register| {
-            if register.fields.is_some() {
-                let register_name = &register.name;
-                let register_name_ident = format_ident!("{}", register_name.to_snake_case().trim());
+            let register_name_ident = snake_ident(&register.name);
+            let register_module = gen_register_module(register, config, atomic);
 
-                quote! {
-                    pub mod #register_name_ident;
+            quote! {
+                pub mod #register_name_ident {
+                    #register_module
                 }
-            } else {
-                quote! {}
             }
-        })
-        .collect();
-
-    let mut register_block_fields = quote! {};
-    let mut previous_offset = 0;
+        });
 
-    for (i, register) in peripheral.registers.iter().enumerate() {
-        let offset = register.offset;
+    let cluster_mods = clusters.iter().map(|cluster| {
+        let cluster_name_ident = snake_ident(&cluster.name);
+        let child_mods = gen_inline_child_mods(
+            &cluster.registers,
+            cluster.clusters(),
+            config,
+            &atomic.in_cluster(cluster),
+        );
+        let cluster_items = register_block::gen_cluster_items(cluster, child_mods, config);
 
-        // Add reserved fields if there's a gap
-        let reserved_count = calculate_reserved_region(previous_offset, offset, None);
-        if reserved_count > 0 {
-            let reserved_field = format_ident!("_reserved{}", i);
-            register_block_fields = quote! {
-                #register_block_fields
-                pub #reserved_field: [u32; #reserved_count],
-            };
+        quote! {
+            pub mod #cluster_name_ident {
+                #cluster_items
+            }
         }
+    });
 
-        let reg_name = format_ident!("{}", register.name.to_lowercase());
-        let reg_type = if register.fields.is_some() {
-            let reg_module_name = format_ident!("{}", register.name.to_lowercase());
-            let reg_struct_name =
-                format_ident!("{}Register", register.name.to_pascal_case().trim());
-            quote! { #reg_module_name::#reg_struct_name }
-        } else {
-            match &register.access {
-                Access::ReadOnly => quote! { volatile_register::RO<u32> },
-                Access::WriteOnly => quote! { volatile_register::WO<u32> },
-                Access::ReadWrite | Access::WriteToClear => quote! { volatile_register::RW<u32> },
-            }
-        };
+    register_mods.chain(cluster_mods).collect()
+}
 
-        register_block_fields = quote! {
-            #register_block_fields
-            pub #reg_name: #reg_type,
-        };
+/// Generates the initial contents of the user extension file of a peripheral.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral the extension file belongs to.
+///
+/// # Returns
+/// The contents of the extension file.
+fn gen_extension_file(peripheral: &Peripheral) -> String {
+    format!(
+        r#"//! Hand-written extensions for the {name} peripheral.
+//!
+//! This file is created once by rust-pac-gen and is never overwritten or deleted.
+//! Everything public in here is re-exported from the {name} peripheral module.
+
+#[allow(unused_imports)]
+use super::*;
+"#,
+        name = peripheral.name
+    )
+}
 
-        previous_offset = offset + (register.size / 8);
+/// Generates the top level module for a peripheral.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral to generate the module for.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the generated module.
+pub fn gen_peripheral_module(peripheral: &Peripheral, config: &Config) -> TokenStream {
+    // The top level module includes the RegisterBlock and declarations for the register
+    // and cluster modules
+    let mut register_mod_decls = gen_child_mod_decls(&peripheral.registers, peripheral.clusters());
+
+    if config.extension_files {
+        register_mod_decls.push(quote! {
+            mod ext;
+            #[allow(unused_imports)]
+            pub use ext::*;
+        });
     }
 
+    gen_peripheral_items(peripheral, register_mod_decls, config)
+}
+
+/// Generates a peripheral module with its register modules inlined.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral to generate the module for.
+/// config: &Config - The configuration file that contains the generation options.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the peripheral.
+///
+/// # Returns
+/// A TokenStream representing the `pub mod` item for the peripheral.
+fn gen_inline_peripheral_module(
+    peripheral: &Peripheral,
+    config: &Config,
+    atomic: &AtomicAccess,
+) -> TokenStream {
+    let register_mods =
+        gen_inline_child_mods(&peripheral.registers, peripheral.clusters(), config, atomic);
+
+    let peripheral_name_ident = snake_ident(&peripheral.name);
+    let peripheral_items = gen_peripheral_items(peripheral, register_mods, config);
+
+    // The feature gates are those of the including crate, which may not declare them all
+    quote! {
+        #[allow(unexpected_cfgs)]
+        pub mod #peripheral_name_ident {
+            #peripheral_items
+        }
+    }
+}
+
+/// Generates the items of a peripheral module: the imports, the given register and
+/// cluster modules and the RegisterBlock.
+///
+/// # Arguments
+/// peripheral: &Peripheral - The peripheral to generate the items for.
+/// register_mods: Vec<TokenStream> - The register and cluster module declarations or inline modules.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the peripheral module items.
+fn gen_peripheral_items(
+    peripheral: &Peripheral,
+    register_mods: Vec<TokenStream>,
+    config: &Config,
+) -> TokenStream {
+    let register_block_fields = register_block::gen_register_block_fields(
+        &peripheral.registers,
+        peripheral.clusters(),
+        None,
+        &config.naming,
+    );
+
     // Next, we need to generate a token stream for the imports
-    // We need to import the volatile_register crate and the register modules
+    // The register modules reach the root items (e.g. the traits) through this module,
+    // which keeps them working when the modules are inlined somewhere other than the crate root.
+    // The register wrappers are re-exported from the root, whether they are inlined or not.
     let imports = quote! {
-        pub use volatile_register::{RO, WO, RW};
-        pub use bit_field::BitField;
+        use super::*;
+        pub use super::{RO, WO, RW};
+        pub use super::BitField;
     };
 
-    // Get the peripheral name but as an identifier in snake case with a 0 suffix
-    let peripheral_name_ident = format_ident!("{}0", peripheral.name.to_snake_case().trim());
+    // The name of the constructor of the peripheral, e.g. uart0
+    let peripheral_name_ident = naming::peripheral_accessor(&peripheral.name, &config.naming);
 
     let peripheral_base_address = peripheral.base_address;
 
+    let interrupt_consts = interrupts::gen_peripheral_interrupts(peripheral);
+
+    // The serde snapshot of the readable registers
+    let snapshot = if config.zero_dependencies {
+        quote! {}
+    } else {
+        serde_impls::gen_snapshot(&peripheral.registers, peripheral.clusters(), &config.naming)
+    };
+
+    // The constructors that map the registers under Linux
+    let mapping_constructors = linux::gen_mapping_constructors(peripheral, config);
+
+    // svd2rust-style accessor methods for the registers and clusters
+    let accessors = if config.svd2rust_api {
+        register_block::gen_register_block_accessors(
+            &peripheral.registers,
+            peripheral.clusters(),
+            &config.naming,
+        )
+    } else {
+        quote! {}
+    };
+
     // Return the token stream
     quote! {
         #imports
 
-        #(#register_mod_decls)*
+        #(#register_mods)*
+
+        #interrupt_consts
 
         #[repr(C)]
         pub struct RegisterBlock {
@@ -313,15 +779,32 @@ pub fn gen_peripheral_module(peripheral: &Peripheral) -> TokenStream {
                 unsafe { &mut *addr }
             }
         }
+
+        #mapping_constructors
+
+        #accessors
+
+        #snapshot
     }
 }
 
 /// Generates the register module for a register.
-pub fn gen_register_module(register: &Register, module_file_path: &PathBuf) {
-    //
-    let register_name = format_ident!("{}", register.name.to_snake_case().trim());
-    let register_struct_name = format_ident!("{}Register", register.name.to_pascal_case().trim());
-    let register_builder_name = format_ident!("{}Builder", register.name.to_pascal_case().trim());
+///
+/// # Arguments
+/// register: &Register - The register to generate the module for. It must have fields.
+/// config: &Config - The configuration file that contains the generation options.
+/// atomic: &AtomicAccess - The atomic access mechanisms of the register block.
+///
+/// # Returns
+/// A TokenStream representing the generated module.
+pub fn gen_register_module(
+    register: &Register,
+    config: &Config,
+    atomic: &AtomicAccess,
+) -> TokenStream {
+    let naming = &config.naming;
+    let register_struct_name = naming::register_type(&register.name, naming);
+    let register_builder_name = naming::builder_type(&register.name, naming);
 
     // Unwrap is safe here because we know that the fields are Some
     let fields = register.fields.as_ref().unwrap();
@@ -333,7 +816,9 @@ pub fn gen_register_module(register: &Register, module_file_path: &PathBuf) {
     let enums = fields
         .iter()
         .filter_map(|field| field.enums.as_ref().map(|e| (e, &field.bit_range)))
-        .map(|(enum_def, bit_range)| generate_enums(enum_def, bit_range, register_type.clone()))
+        .map(|(enum_def, bit_range)| {
+            generate_enums(enum_def, bit_range, register_type.clone(), config)
+        })
         .collect::<Vec<TokenStream>>();
 
     // Get the Rust type that can hold the register size
@@ -345,22 +830,62 @@ pub fn gen_register_module(register: &Register, module_file_path: &PathBuf) {
         generate_from_bits_trait(register_builder_name.clone(), register_type.clone());
 
     // Generate the RegisterRO, RegisterWO, and RegisterRW traits for the register
-    let register_traits = generate_register_traits(register);
+    let register_traits = generate_register_traits(register, naming);
 
     // Generate the default implementation for the register builder
-    let builder_default = generate_register_builder_default(register);
+    let builder_default = generate_register_builder_default(register, naming);
 
     // Generate the builder implementation for the register
-    let builder_impl = generate_builder_impl(register);
+    let builder_impl = generate_builder_impl(register, naming);
+
+    // Generate the svd2rust-style reader and writer proxies if they are enabled
+    let proxies = if config.svd2rust_api {
+        svd2rust::gen_register_proxies(register, naming)
+    } else {
+        quote! {}
+    };
+
+    // Generate the atomic set/clear/toggle helpers if they are enabled
+    let atomics = atomic::gen_register_atomics(register, atomic, naming);
 
-    let register_module = quote! {
-        use crate::*;
+    // Format the builder field by field, for Debug and defmt::Format (behind the defmt feature)
+    let builder_debug = debug::gen_builder_debug(register, naming);
+    let builder_defmt = if config.zero_dependencies {
+        quote! {}
+    } else {
+        debug::gen_builder_defmt(register, naming)
+    };
 
+    // (De)serialize the builder as a map of its fields, behind the serde feature
+    let builder_serde = if config.zero_dependencies {
+        quote! {}
+    } else {
+        serde_impls::gen_builder_serde(register, naming)
+    };
+
+    // Document the register with its description and its bit layout, as in a datasheet
+    let mut doc_lines = vec![format!(" {}", register.description)];
+    if let Some(diagram) = diagram::bit_field_diagram(register) {
+        doc_lines.push(String::new());
+        doc_lines.push(" ```text".to_string());
+        doc_lines.extend(diagram.lines().map(|line| format!(" {}", line)));
+        doc_lines.push(" ```".to_string());
+    }
+    let builder_doc = format!(
+        " The value of [`{}`], built and read field by field",
+        register_struct_name
+    );
+
+    quote! {
+        use super::*;
+
+        #(#[doc = #doc_lines])*
         pub struct #register_struct_name {
-            inner: volatile_register::RW<#register_type>,
+            inner: RW<#register_type>,
         }
 
-        #[derive(Debug, Clone, Copy)]
+        #[doc = #builder_doc]
+        #[derive(Clone, Copy)]
         pub struct #register_builder_name {
             value: #register_type,
         }
@@ -373,35 +898,35 @@ pub fn gen_register_module(register: &Register, module_file_path: &PathBuf) {
 
         #builder_impl
 
-        #(#enums)*
-    };
+        #builder_debug
 
-    // For now, safe off the register_module generation
-    let register_module_formatted = format_token_stream(&register_module);
-    let register_module_path = module_file_path
-        .join(&register_name.to_string())
-        .join("mod.rs");
-    write_to_file_str(&register_module_path, &register_module_formatted).unwrap();
+        #builder_defmt
+
+        #builder_serde
+
+        #proxies
+
+        #atomics
+
+        #(#enums)*
+    }
 }
 
 fn generate_enums(
     enum_def: &Enum,
     bit_range: &BitRange,
     register_type: TokenStream,
+    config: &Config,
 ) -> TokenStream {
     // Get the rust type that can hold the enum size
-    let enum_size = match bit_range {
-        BitRange::Single(bit) => *bit,
-        BitRange::Range(start, end) => end - start + 1,
-    };
-    let enum_type = get_smallest_rust_type(enum_size);
-    let enum_name = format_ident!("{}", enum_def.name.to_pascal_case().trim());
+    let enum_type = enum_repr_type(bit_range);
+    let enum_name = pascal_ident(&enum_def.name);
     let mut variants: Vec<TokenStream> = Vec::new();
     let mut variant_matchers: Vec<TokenStream> = Vec::new();
     let mut default_variant = None;
 
-    for (_i, value) in enum_def.values.iter().enumerate() {
-        let variant_name = format_ident!("{}", value.name.to_pascal_case().trim());
+    for value in &enum_def.values {
+        let variant_name = pascal_ident(&value.name);
         let variant_value = value.value;
         variants.push(quote! {
             #variant_name = #variant_value as #enum_type,
@@ -418,6 +943,20 @@ fn generate_enums(
 
     let default_variant = default_variant.expect("Enum must have at least one variant");
 
+    // The enums can be logged with defmt and (de)serialized with serde, behind the
+    // features of the same name in the generated crate
+    let feature_impls = if config.zero_dependencies {
+        quote! {}
+    } else {
+        let enum_defmt = debug::gen_enum_defmt(enum_def);
+        let enum_serde = serde_impls::gen_enum_serde(enum_def);
+        quote! {
+            #enum_defmt
+
+            #enum_serde
+        }
+    };
+
     quote! {
         #[repr(#enum_type)]
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -425,6 +964,8 @@ fn generate_enums(
             #(#variants)*
         }
 
+        #feature_impls
+
         impl Default for #enum_name {
             fn default() -> Self {
                 Self::#default_variant
@@ -467,6 +1008,21 @@ fn generate_enums(
     }
 }
 
+/// Returns the Rust type used as the representation of the enum of a field.
+///
+/// # Arguments
+/// bit_range: &BitRange - The bit range of the field.
+///
+/// # Returns
+/// A TokenStream representing the type.
+fn enum_repr_type(bit_range: &BitRange) -> TokenStream {
+    let enum_size = match bit_range {
+        BitRange::Single(bit) => *bit,
+        BitRange::Range(start, end) => end - start + 1,
+    };
+    get_smallest_rust_type(enum_size)
+}
+
 /// Generates the FromBits trait implementation for a builder struct.
 ///
 /// # Arguments
@@ -489,14 +1045,13 @@ fn generate_from_bits_trait(builder_name: Ident, register_type: TokenStream) ->
     }
 }
 
-fn generate_register_traits(register_def: &Register) -> TokenStream {
+fn generate_register_traits(register_def: &Register, naming: &Naming) -> TokenStream {
     // Generate the RegisterRO, RegisterWO, and/or RegisterRW traits based on the access
     // All read-only registers will ONLY implement the RegisterRO trait
     // All write-only registers will ONLY implement the RegisterWO trait
     // Read-write registers will implement RegisterRO, RegisterWO, and RegisterRW
-    let register_name = format_ident!("{}Register", register_def.name.to_pascal_case().trim());
-    let register_builder_name =
-        format_ident!("{}Builder", register_def.name.to_pascal_case().trim());
+    let register_name = naming::register_type(&register_def.name, naming);
+    let register_builder_name = naming::builder_type(&register_def.name, naming);
     let register_type = get_smallest_rust_type(register_def.size);
 
     match &register_def.access {
@@ -545,9 +1100,9 @@ fn generate_register_traits(register_def: &Register) -> TokenStream {
                     where
                         F: FnOnce(#register_builder_name) -> #register_builder_name,
                     {
-                        let value = self.read();
+                        let value = RegisterRO::read(self);
                         let modified = f(value);
-                        self.write(modified);
+                        RegisterWO::write(self, modified);
                     }
                 }
             }
@@ -555,74 +1110,165 @@ fn generate_register_traits(register_def: &Register) -> TokenStream {
     }
 }
 
-fn generate_register_builder_default(register_def: &Register) -> TokenStream {
-    let register_builder_name =
-        format_ident!("{}Builder", register_def.name.to_pascal_case().trim());
+fn generate_register_builder_default(register_def: &Register, naming: &Naming) -> TokenStream {
+    let register_builder_name = naming::builder_type(&register_def.name, naming);
 
     let fields = register_def.fields.as_ref().unwrap();
     let register_type = get_smallest_rust_type(register_def.size);
 
     let set_defaults = fields.iter().filter_map(|field| {
+        let (start, end) = field.bit_range.get_bit_range();
+        let mask = bit_range_mask(start, end);
+
         if let Some(default_value) = field.default_value {
-            let (start, end) = field.bit_range.get_bit_range();
-            let is_single_bit = start == end;
-            let field_value = if is_single_bit {
-                let converted_value = usize_to_bool(start);
-                quote! { #converted_value }
-            } else {
-                quote! { #default_value }
-            };
-            if is_single_bit {
+            if field.bit_range.is_single_bit() {
+                let field_value = usize_to_bool(default_value);
                 Some(quote! {
-                    value.set_bit(#start, #field_value);
+                    value = (value & !#mask) | ((#field_value as #register_type) << #start);
                 })
             } else {
                 Some(quote! {
-                    value.set_bits(#start..=#end, #field_value);
+                    value = (value & !#mask) | ((#default_value as #register_type) << #start & #mask);
                 })
             }
         } else if let Some(enum_def) = &field.enums {
-            let (start, end) = field.bit_range.get_bit_range();
-            let enum_name = format_ident!("{}", enum_def.name.to_pascal_case().trim());
+            let enum_name = pascal_ident(&enum_def.name);
+            // The default variant is the one marked as default, or the first one
             let default_variant = enum_def
                 .values
                 .iter()
                 .find(|v| v.is_default.unwrap_or(false))
+                .or(enum_def.values.first())
                 .expect("Enum must have at least one variant");
             let default_variant_name =
-                format_ident!("{}", default_variant.name.to_pascal_case().trim());
+                pascal_ident(&default_variant.name);
             Some(quote! {
-                value.set_bits(#start..=#end, #enum_name::#default_variant_name as #register_type);
+                value = (value & !#mask)
+                    | ((#enum_name::#default_variant_name as #register_type) << #start & #mask);
             })
         } else {
             None
         }
     });
+    let set_defaults: Vec<_> = set_defaults.collect();
+
+    // The value is only reassigned when at least one field has a default
+    let binding = if set_defaults.is_empty() {
+        quote! { value }
+    } else {
+        quote! { mut value }
+    };
 
     quote! {
-        impl Default for #register_builder_name {
-            fn default() -> Self {
-                let mut value: #register_type = 0;
+        impl #register_builder_name {
+            /// Returns the reset value of the register, usable in const contexts
+            pub const fn default_const() -> Self {
+                let #binding: #register_type = 0;
 
                 // Set default values for fields
                 #(#set_defaults)*
 
                 Self { value }
             }
+
+            /// Creates a builder from the raw bits of the register
+            pub const fn from_bits(bits: #register_type) -> Self {
+                Self { value: bits }
+            }
+
+            /// Returns the raw bits of the register
+            pub const fn into_bits(&self) -> #register_type {
+                self.value
+            }
         }
+
+        impl Default for #register_builder_name {
+            fn default() -> Self {
+                Self::default_const()
+            }
+        }
+    }
+}
+
+/// Generates the per-variant methods of an enum field of a builder:
+/// an `is_<field>_<variant>()` predicate for every variant if the field is readable,
+/// and a `with_<field>_<variant>()` setter for every variant if the field is writable.
+///
+/// # Arguments
+/// field: &Field - The enum field to generate the methods for.
+/// enum_def: &Enum - The enum of the field.
+/// is_readable: bool - Whether the field can be read.
+/// is_writable: bool - Whether the field can be written.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the generated methods.
+fn generate_variant_methods(
+    field: &Field,
+    enum_def: &Enum,
+    is_readable: bool,
+    is_writable: bool,
+    naming: &Naming,
+) -> TokenStream {
+    let field_name = snake_ident(&field.name);
+    let with_field_name = naming::setter(&field.name, naming);
+    let enum_name = pascal_ident(&enum_def.name);
+
+    let methods = enum_def.values.iter().map(|value| {
+        let variant_name = pascal_ident(&value.name);
+        let predicate_name = naming::variant_predicate(&field.name, &value.name);
+        let setter_name = naming::variant_setter(&field.name, &value.name, naming);
+
+        let predicate = if is_readable {
+            quote! {
+                pub const fn #predicate_name(&self) -> bool {
+                    matches!(self.#field_name(), Some(#enum_name::#variant_name))
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let setter = if is_writable {
+            quote! {
+                pub const fn #setter_name(self) -> Self {
+                    self.#with_field_name(#enum_name::#variant_name)
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            #predicate
+            #setter
+        }
+    });
+
+    quote! {
+        #(#methods)*
     }
 }
 
-fn generate_builder_impl(register_def: &Register) -> TokenStream {
-    let register_builder_name =
-        format_ident!("{}Builder", register_def.name.to_pascal_case().trim());
+/// Generates the getters and setters of a builder.
+/// They are const fns, so they use shift and mask arithmetic instead of the BitField trait.
+///
+/// # Arguments
+/// register_def: &Register - The register to generate the builder methods for.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the builder implementation.
+fn generate_builder_impl(register_def: &Register, naming: &Naming) -> TokenStream {
+    let register_builder_name = naming::builder_type(&register_def.name, naming);
     let fields = register_def.fields.as_ref().unwrap();
     let register_size = get_smallest_rust_type(register_def.size);
 
     let methods = fields.iter().map(|field| {
-        let field_name = format_ident!("{}", field.name.to_snake_case().trim());
-        let with_field_name = format_ident!("with_{}", field.name.to_snake_case().trim());
+        let field_name = snake_ident(&field.name);
+        let with_field_name = naming::setter(&field.name, naming);
         let (start, end) = field.bit_range.get_bit_range();
+        let mask = bit_range_mask(start, end);
         let is_single_bit = field.bit_range.is_single_bit();
         let field_access = field.access.as_ref().unwrap_or(&Access::ReadWrite);
 
@@ -631,7 +1277,7 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
         // If it is a single bit, we can return a boolean
         // If it is a range, we can get the smallest Rust type that can hold the range
         let field_type = if let Some(enum_def) = &field.enums {
-            let enum_name = format_ident!("{}", enum_def.name.to_pascal_case().trim());
+            let enum_name = pascal_ident(&enum_def.name);
             quote! { #enum_name }
         } else if is_single_bit {
             quote! { bool }
@@ -641,11 +1287,22 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
         };
 
         if let Some(enum_def) = &field.enums {
-            let enum_name = format_ident!("{}", enum_def.name.to_pascal_case().trim());
+            let enum_name = pascal_ident(&enum_def.name);
             let getter_method = if matches!(field_access, Access::ReadOnly | Access::ReadWrite) {
+                // TryFrom is not const, so the variants are matched here
+                let variant_matchers = enum_def.values.iter().map(|value| {
+                    let variant_name = pascal_ident(&value.name);
+                    let variant_value = proc_macro2::Literal::usize_unsuffixed(value.value);
+                    quote! {
+                        #variant_value => Some(#enum_name::#variant_name),
+                    }
+                });
                 quote! {
-                    pub fn #field_name(&self) -> Option<#enum_name> {
-                        #enum_name::try_from(self.value.get_bits(#start..=#end)).ok()
+                    pub const fn #field_name(&self) -> Option<#enum_name> {
+                        match (self.value & #mask) >> #start {
+                            #(#variant_matchers)*
+                            _ => None,
+                        }
                     }
                 }
             } else {
@@ -657,8 +1314,8 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
                 Access::WriteOnly | Access::WriteToClear | Access::ReadWrite
             ) {
                 quote! {
-                    pub fn #with_field_name(mut self, value: #enum_name) -> Self {
-                        self.value.set_bits(#start..=#end, Into::<#register_size>::into(value));
+                    pub const fn #with_field_name(mut self, value: #enum_name) -> Self {
+                        self.value = (self.value & !#mask) | ((value as #register_size) << #start & #mask);
                         self
                     }
                 }
@@ -666,22 +1323,31 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
                 quote! {}
             };
 
+            let variant_methods = generate_variant_methods(
+                field,
+                enum_def,
+                !getter_method.is_empty(),
+                !setter_method.is_empty(),
+                naming,
+            );
+
             quote! {
                 #getter_method
                 #setter_method
+                #variant_methods
             }
         } else {
             let getter_method = if matches!(field_access, Access::ReadOnly | Access::ReadWrite) {
                 if is_single_bit {
                     quote! {
-                        pub fn #field_name(&self) -> bool {
-                            self.value.get_bit(#start)
+                        pub const fn #field_name(&self) -> bool {
+                            self.value & #mask != 0
                         }
                     }
                 } else {
                     quote! {
-                        pub fn #field_name(&self) -> #field_type {
-                            self.value.get_bits(#start..=#end) as #field_type
+                        pub const fn #field_name(&self) -> #field_type {
+                            ((self.value & #mask) >> #start) as #field_type
                         }
                     }
                 }
@@ -692,15 +1358,15 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
             let setter_method = if matches!(field_access, Access::WriteOnly | Access::ReadWrite) {
                 if is_single_bit {
                     quote! {
-                        pub fn #with_field_name(mut self, value: bool) -> Self {
-                            self.value.set_bit(#start, value);
+                        pub const fn #with_field_name(mut self, value: bool) -> Self {
+                            self.value = (self.value & !#mask) | ((value as #register_size) << #start);
                             self
                         }
                     }
                 } else {
                     quote! {
-                        pub fn #with_field_name(mut self, value: #field_type) -> Self {
-                            self.value.set_bits(#start..=#end, value as #register_size);
+                        pub const fn #with_field_name(mut self, value: #field_type) -> Self {
+                            self.value = (self.value & !#mask) | ((value as #register_size) << #start & #mask);
                             self
                         }
                     }
@@ -709,9 +1375,34 @@ fn generate_builder_impl(register_def: &Register) -> TokenStream {
                 quote! {}
             };
 
+            // Single-bit fields also get set_, clear_ and toggle_ helpers, except the one
+            // named like the setter when the setter prefix is e.g. set_
+            let bit_methods = if is_single_bit && !setter_method.is_empty() {
+                let helpers = [
+                    ("set", quote! { self.value |= #mask; }),
+                    ("clear", quote! { self.value &= !#mask; }),
+                    ("toggle", quote! { self.value ^= #mask; }),
+                ];
+                let helpers = helpers.into_iter().filter_map(|(operation, body)| {
+                    let helper_name = naming::bit_helper(operation, &field.name);
+                    (helper_name != with_field_name).then(|| {
+                        quote! {
+                            pub const fn #helper_name(mut self) -> Self {
+                                #body
+                                self
+                            }
+                        }
+                    })
+                });
+                quote! { #(#helpers)* }
+            } else {
+                quote! {}
+            };
+
             quote! {
                 #getter_method
                 #setter_method
+                #bit_methods
             }
         }
     });
@@ -0,0 +1,433 @@
+//! The gen::naming module holds the naming conventions of the generated code, as set in
+//! the `[naming]` table of the configuration file. Every name that is made of a name of
+//! the description and a fixed part (`ModeRegister`, `with_char_len`, `uart0`) is built
+//! here, on top of the gen::ident module that turns the names into valid identifiers.
+//!
+//! The renames and the prefix stripping change the names of the description itself, with
+//! `apply` before anything is generated, so every part of the generated crate sees the
+//! same names.
+
+use std::collections::HashSet;
+
+use proc_macro2::Ident;
+
+use super::ident::{ident, pascal, snake};
+use crate::{
+    config::Naming,
+    parse::{Cluster, PeripheralAccess, Register},
+};
+
+/// The name of the register struct of a register, e.g. `ModeRegister`.
+pub fn register_type(register_name: &str, naming: &Naming) -> Ident {
+    ident(&format!(
+        "{}{}",
+        pascal(register_name),
+        naming.register_suffix
+    ))
+}
+
+/// The name of the builder of a register, e.g. `ModeBuilder`.
+pub fn builder_type(register_name: &str, naming: &Naming) -> Ident {
+    ident(&format!(
+        "{}{}",
+        pascal(register_name),
+        naming.builder_suffix
+    ))
+}
+
+/// The name of the enum of the fields of a register used by serde, e.g. `ModeFields`.
+pub fn fields_type(register_name: &str) -> Ident {
+    ident(&format!("{}Fields", pascal(register_name)))
+}
+
+/// The name of the svd2rust-style register spec of a register, e.g. `ModeSpec`.
+pub fn spec_type(register_name: &str) -> Ident {
+    ident(&format!("{}Spec", pascal(register_name)))
+}
+
+/// The name of the builder setter of a field, e.g. `with_char_len`.
+pub fn setter(field_name: &str, naming: &Naming) -> Ident {
+    ident(&format!("{}{}", naming.setter_prefix, snake(field_name)))
+}
+
+/// The name of the builder setter of a variant of an enum field, e.g. `with_char_len_six_bits`.
+pub fn variant_setter(field_name: &str, variant_name: &str, naming: &Naming) -> Ident {
+    ident(&format!(
+        "{}{}_{}",
+        naming.setter_prefix,
+        snake(field_name),
+        snake(variant_name)
+    ))
+}
+
+/// The name of the builder predicate of a variant of an enum field, e.g. `is_char_len_six_bits`.
+pub fn variant_predicate(field_name: &str, variant_name: &str) -> Ident {
+    ident(&format!("is_{}_{}", snake(field_name), snake(variant_name)))
+}
+
+/// The name of a set, clear or toggle helper of a single-bit field, e.g. `set_clk_sel`.
+pub fn bit_helper(operation: &str, field_name: &str) -> Ident {
+    ident(&format!("{}_{}", operation, snake(field_name)))
+}
+
+/// The name of an atomic set, clear or toggle helper of a single-bit field,
+/// e.g. `set_clk_sel_atomic`.
+pub fn atomic_bit_helper(operation: &str, field_name: &str) -> Ident {
+    ident(&format!("{}_{}_atomic", operation, snake(field_name)))
+}
+
+/// The name of the iterator accessor of a cluster array, e.g. `ch_iter`.
+pub fn cluster_iter(cluster_name: &str) -> Ident {
+    ident(&format!("{}_iter", snake(cluster_name)))
+}
+
+/// The name of the svd2rust-style reader proxy of a field, e.g. `CharLenR`.
+pub fn field_reader(field_name: &str) -> Ident {
+    ident(&format!("{}R", pascal(field_name)))
+}
+
+/// The name of the svd2rust-style writer proxy of a field, e.g. `CharLenW`.
+pub fn field_writer(field_name: &str) -> Ident {
+    ident(&format!("{}W", pascal(field_name)))
+}
+
+/// The name of the predicate of a variant on an svd2rust-style reader proxy, e.g. `is_six_bits`.
+pub fn reader_predicate(variant_name: &str) -> Ident {
+    ident(&format!("is_{}", snake(variant_name)))
+}
+
+/// The methods of the svd2rust-style `FieldWriter`, which the variant setters must not shadow.
+const FIELD_WRITER_METHODS: &[&str] = &["new", "bits", "variant"];
+
+/// The name of the setter of a variant on an svd2rust-style writer proxy, e.g. `six_bits`.
+/// A variant named like a method of the writer, such as `Bits`, gets a `_` suffix (`bits_`).
+pub fn variant_writer(variant_name: &str) -> Ident {
+    let name = snake(variant_name);
+    if FIELD_WRITER_METHODS.contains(&name.as_str()) {
+        ident(&format!("{}_", name))
+    } else {
+        ident(&name)
+    }
+}
+
+/// The name of the RegisterBlock constructor of a peripheral, e.g. `uart0`.
+pub fn peripheral_accessor(peripheral_name: &str, naming: &Naming) -> Ident {
+    ident(
+        &naming
+            .peripheral_accessor
+            .replace("{name}", &snake(peripheral_name)),
+    )
+}
+
+/// Removes a prefix from a name, if the name starts with it (ignoring the case) followed
+/// by a separator, and what is left still starts with a letter (`UART_MODE` becomes `MODE`,
+/// but `UART_0` is kept).
+fn strip_prefix(name: &str, prefix: &str) -> String {
+    let stripped = name
+        .get(..prefix.len())
+        .filter(|start| !prefix.is_empty() && start.eq_ignore_ascii_case(prefix))
+        .and_then(|_| name[prefix.len()..].strip_prefix(['_', '-', '.', ' ']))
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()));
+
+    stripped.unwrap_or(name).to_string()
+}
+
+/// The renames and prefix stripping of a run, with the renames that were used.
+struct Renamer<'a> {
+    naming: &'a Naming,
+    used: HashSet<&'a str>,
+}
+
+impl<'a> Renamer<'a> {
+    /// Returns the new name of the item at the path, if it is renamed.
+    fn rename(&mut self, path: &str) -> Option<String> {
+        let (key, name) = self.naming.rename.get_key_value(path)?;
+        self.used.insert(key);
+        Some(name.clone())
+    }
+
+    /// Renames the registers and clusters of a register block and everything in them.
+    fn rename_block(
+        &mut self,
+        path: &str,
+        peripheral_name: &str,
+        registers: &mut [Register],
+        clusters: &mut [Cluster],
+    ) {
+        for register in registers {
+            let register_path = format!("{}.{}", path, register.name);
+            let original_name = register.name.clone();
+            register.name = match self.rename(&register_path) {
+                Some(name) => name,
+                None if self.naming.strip_prefixes => strip_prefix(&original_name, peripheral_name),
+                None => original_name.clone(),
+            };
+
+            for field in register.fields.iter_mut().flatten() {
+                let field_path = format!("{}.{}", register_path, field.name);
+                field.name = match self.rename(&field_path) {
+                    Some(name) => name,
+                    None if self.naming.strip_prefixes => {
+                        let name = strip_prefix(&field.name, peripheral_name);
+                        let name = strip_prefix(&name, &original_name);
+                        strip_prefix(&name, &register.name)
+                    }
+                    None => field.name.clone(),
+                };
+            }
+        }
+
+        for cluster in clusters {
+            let cluster_path = format!("{}.{}", path, cluster.name);
+            self.rename_block(
+                &cluster_path,
+                peripheral_name,
+                &mut cluster.registers,
+                cluster.clusters.as_deref_mut().unwrap_or_default(),
+            );
+            if let Some(name) = self.rename(&cluster_path) {
+                cluster.name = name;
+            }
+        }
+    }
+}
+
+/// Applies the renames and the prefix stripping of the naming conventions to a description.
+///
+/// # Arguments
+/// pac: &mut PeripheralAccess - The parsed peripheral access data structure.
+/// naming: &Naming - The naming conventions.
+///
+/// # Returns
+/// The paths of the renames that matched nothing in the description.
+pub fn apply(pac: &mut PeripheralAccess, naming: &Naming) -> Vec<String> {
+    let mut renamer = Renamer {
+        naming,
+        used: HashSet::new(),
+    };
+
+    for peripheral in &mut pac.peripherals {
+        let path = peripheral.name.clone();
+        renamer.rename_block(
+            &path,
+            &path,
+            &mut peripheral.registers,
+            peripheral.clusters.as_deref_mut().unwrap_or_default(),
+        );
+        if let Some(name) = renamer.rename(&path) {
+            peripheral.name = name;
+        }
+    }
+
+    naming
+        .rename
+        .keys()
+        .filter(|path| !renamer.used.contains(path.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Finds the naming conventions that would generate the same name for different items,
+/// whatever the description.
+///
+/// # Arguments
+/// naming: &Naming - The naming conventions.
+///
+/// # Returns
+/// A description of every conflict.
+pub fn find_conflicts(naming: &Naming) -> Vec<String> {
+    let mut conflicts = Vec::new();
+
+    if naming.register_suffix == naming.builder_suffix {
+        conflicts.push(format!(
+            "naming: register structs and builders are both named `<Register>{}`",
+            naming.register_suffix
+        ));
+    }
+    if naming.setter_prefix.is_empty() {
+        conflicts.push("naming: builder setters are named like the getters".to_string());
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::gen::ident::find_collisions;
+
+    /// A description with a UART peripheral with the given registers.
+    fn description(registers: serde_json::Value) -> PeripheralAccess {
+        serde_json::from_value(json!({
+            "name": "Test",
+            "register_size": 32,
+            "peripherals": [{
+                "name": "UART",
+                "base_address": "0xE0000000",
+                "registers": registers
+            }]
+        }))
+        .unwrap()
+    }
+
+    /// A read-write register at the offset with the given fields.
+    fn register(name: &str, offset: &str, fields: serde_json::Value) -> serde_json::Value {
+        json!({
+            "name": name,
+            "access": "read-write",
+            "description": "",
+            "offset": offset,
+            "size": 32,
+            "fields": fields
+        })
+    }
+
+    /// A single-bit field at the bit.
+    fn field(name: &str, bit: u32) -> serde_json::Value {
+        json!({ "name": name, "bit_range": bit.to_string(), "description": "" })
+    }
+
+    /// The names of the registers of the UART, each with the names of its fields.
+    fn names(pac: &PeripheralAccess) -> Vec<(String, Vec<String>)> {
+        pac.peripherals[0]
+            .registers
+            .iter()
+            .map(|register| {
+                let fields = register.fields.iter().flatten();
+                let fields = fields.map(|field| field.name.clone()).collect();
+                (register.name.clone(), fields)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prefixes_are_stripped_only_before_a_separator() {
+        assert_eq!(strip_prefix("UART_MODE", "UART"), "MODE");
+        assert_eq!(strip_prefix("uart.mode", "UART"), "mode");
+        assert_eq!(strip_prefix("UARTMODE", "UART"), "UARTMODE");
+        assert_eq!(strip_prefix("MODE", ""), "MODE");
+    }
+
+    #[test]
+    fn prefixes_are_kept_when_nothing_or_a_digit_would_be_left() {
+        assert_eq!(strip_prefix("UART", "UART"), "UART");
+        assert_eq!(strip_prefix("UART_", "UART"), "UART_");
+        assert_eq!(strip_prefix("UART_0", "UART"), "UART_0");
+    }
+
+    #[test]
+    fn apply_strips_the_peripheral_and_register_prefixes() {
+        let mut pac = description(json!([
+            register(
+                "UART_MODE",
+                "0x0",
+                json!([
+                    field("UART_MODE_TX_EN", 0),
+                    field("MODE_RX_EN", 1),
+                    field("MODE_1", 2)
+                ])
+            ),
+            register("UART", "0x4", json!([field("UART", 0)]))
+        ]));
+        let naming = Naming {
+            strip_prefixes: true,
+            ..Naming::default()
+        };
+
+        assert!(apply(&mut pac, &naming).is_empty());
+        assert_eq!(
+            names(&pac),
+            [
+                (
+                    "MODE".to_string(),
+                    vec![
+                        "TX_EN".to_string(),
+                        "RX_EN".to_string(),
+                        "MODE_1".to_string()
+                    ]
+                ),
+                ("UART".to_string(), vec!["UART".to_string()])
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_renames_by_path_and_reports_the_unused_renames() {
+        let mut pac = description(json!([register(
+            "UART_MODE",
+            "0x0",
+            json!([field("UART_MODE_TX_EN", 0)])
+        )]));
+        let naming = Naming {
+            strip_prefixes: true,
+            rename: [
+                ("UART", "SERIAL"),
+                ("UART.UART_MODE", "CONTROL"),
+                ("UART.UART_MODE.UART_MODE_TX_EN", "transmit"),
+                ("UART.STATUS", "FLAGS"),
+            ]
+            .into_iter()
+            .map(|(path, name)| (path.to_string(), name.to_string()))
+            .collect(),
+            ..Naming::default()
+        };
+
+        // The paths are the ones of the description, not of the renamed or stripped names
+        assert_eq!(apply(&mut pac, &naming), ["UART.STATUS"]);
+        assert_eq!(pac.peripherals[0].name, "SERIAL");
+        assert_eq!(
+            names(&pac),
+            [("CONTROL".to_string(), vec!["transmit".to_string()])]
+        );
+    }
+
+    #[test]
+    fn a_rename_to_an_existing_name_collides() {
+        let mut pac = description(json!([register(
+            "MODE",
+            "0x0",
+            json!([field("tx_en", 0), field("rx_en", 1)])
+        )]));
+        let naming = Naming {
+            rename: [("UART.MODE.rx_en".to_string(), "TX_EN".to_string())].into(),
+            ..Naming::default()
+        };
+
+        assert!(apply(&mut pac, &naming).is_empty());
+        assert_eq!(
+            find_collisions(&pac),
+            ["UART.MODE: tx_en and TX_EN are both generated as `tx_en`"]
+        );
+    }
+
+    #[test]
+    fn the_default_naming_has_no_conflicts() {
+        assert!(find_conflicts(&Naming::default()).is_empty());
+    }
+
+    #[test]
+    fn naming_conventions_that_generate_the_same_name_conflict() {
+        let naming = Naming {
+            builder_suffix: "Register".to_string(),
+            setter_prefix: String::new(),
+            ..Naming::default()
+        };
+        assert_eq!(
+            find_conflicts(&naming),
+            [
+                "naming: register structs and builders are both named `<Register>Register`",
+                "naming: builder setters are named like the getters"
+            ]
+        );
+    }
+
+    #[test]
+    fn variant_writers_do_not_shadow_the_field_writer_methods() {
+        assert_eq!(variant_writer("SixBits").to_string(), "six_bits");
+        assert_eq!(variant_writer("Bits").to_string(), "bits_");
+        assert_eq!(variant_writer("VARIANT").to_string(), "variant_");
+        assert_eq!(variant_writer("new").to_string(), "new_");
+    }
+}
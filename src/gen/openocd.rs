@@ -0,0 +1,163 @@
+//! The gen::openocd module generates an OpenOCD Tcl script with a proc per readable
+//! register, which reads the register over the debug adapter and prints its decoded fields.
+//!
+//! For the MODE register of the UART peripheral, `uart_mode_show` prints:
+//!
+//! ```text
+//! UART.MODE @ 0xE0001004 = 0x00000020
+//!   clk_sel              [0]     = 0x0
+//!   char_len             [2:1]   = 0x0 (EightBits)
+//!   parity               [5:3]   = 0x4 (None)
+//! ```
+//!
+//! and `uart_show` prints every readable register of the peripheral.
+
+use std::fmt::Write;
+
+use crate::parse::{utils::Access, Field, PeripheralAccess, Register};
+
+/// The generic procs used by the register procs.
+const HELPERS: &str = r#"# Reads a register of the given width in bits from the target (OpenOCD 0.12 or later)
+proc pac_read {address width} {
+    return [read_memory $address $width 1]
+}
+
+# Extracts the field of the given width at the given bit from a register value
+proc pac_field {value lsb width} {
+    return [expr {($value >> $lsb) & ((1 << $width) - 1)}]
+}
+
+# Prints a field of a register, with the name of its variant if it has one
+proc pac_show_field {name bits value {variants {}}} {
+    if {[dict exists $variants $value]} {
+        echo [format {  %-20s %-7s = 0x%X (%s)} $name $bits $value [dict get $variants $value]]
+    } else {
+        echo [format {  %-20s %-7s = 0x%X} $name $bits $value]
+    }
+}
+"#;
+
+/// Returns the name of the Tcl proc for a register path, e.g. "uart_mode" for "UART.MODE".
+///
+/// # Arguments
+/// path: &str - The path of the register, including the peripheral name.
+///
+/// # Returns
+/// The path in lower case, with every character that is not alphanumeric replaced by `_`.
+pub fn proc_name(path: &str) -> String {
+    path.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Returns the fields of a register that can be read, as printed by the debug scripts.
+pub fn readable_fields(register: &Register) -> impl Iterator<Item = &Field> {
+    register.fields.iter().flatten().filter(|field| {
+        !matches!(
+            field.access.as_ref().unwrap_or(&register.access),
+            Access::WriteOnly
+        )
+    })
+}
+
+/// Returns the bit range of a field as it is printed by the debug scripts, e.g. "[2:1]".
+pub fn bits_label(field: &Field) -> String {
+    let (start, end) = field.bit_range.get_bit_range();
+    if start == end {
+        format!("[{}]", start)
+    } else {
+        format!("[{}:{}]", end, start)
+    }
+}
+
+/// Generates the OpenOCD Tcl script for the peripheral access crate.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The contents of the Tcl script.
+pub fn gen_openocd_script(pac: &PeripheralAccess) -> String {
+    let mut script = String::new();
+    let _ = writeln!(
+        script,
+        "# OpenOCD helpers for {}, generated by rust-pac-gen. Do not edit.",
+        pac.name
+    );
+    let _ = writeln!(
+        script,
+        "# Load with `source <path to this file>`, then run e.g. `<peripheral>_<register>_show`."
+    );
+    script.push('\n');
+    script.push_str(HELPERS);
+
+    for peripheral in &pac.peripherals {
+        let mut register_procs = Vec::new();
+
+        for flat_register in peripheral.flat_registers() {
+            let register = flat_register.register;
+            if matches!(register.access, Access::WriteOnly) {
+                continue;
+            }
+
+            let path = format!("{}.{}", peripheral.name, flat_register.path);
+            let name = proc_name(&path);
+            let address = peripheral.base_address + flat_register.offset;
+
+            script.push('\n');
+            let _ = writeln!(script, "# {}: {}", path, register.description);
+            let _ = writeln!(script, "proc {}_show {{}} {{", name);
+            let _ = writeln!(
+                script,
+                "    set value [pac_read 0x{:08X} {}]",
+                address, register.size
+            );
+            let _ = writeln!(
+                script,
+                "    echo [format {{{} @ 0x%08X = 0x%08X}} 0x{:08X} $value]",
+                path, address
+            );
+            for field in readable_fields(register) {
+                let (start, end) = field.bit_range.get_bit_range();
+                let variants = field
+                    .enums
+                    .iter()
+                    .flat_map(|enum_def| &enum_def.values)
+                    .map(|value| format!("{} {}", value.value, value.name))
+                    .collect::<Vec<_>>();
+                let _ = write!(
+                    script,
+                    "    pac_show_field {} {{{}}} [pac_field $value {} {}]",
+                    field.name,
+                    bits_label(field),
+                    start,
+                    end - start + 1
+                );
+                if !variants.is_empty() {
+                    let _ = write!(script, " {{{}}}", variants.join(" "));
+                }
+                script.push('\n');
+            }
+            script.push_str("}\n");
+
+            register_procs.push(name);
+        }
+
+        // A proc to show every readable register of the peripheral
+        script.push('\n');
+        let _ = writeln!(script, "# Every readable register of {}", peripheral.name);
+        let _ = writeln!(script, "proc {}_show {{}} {{", proc_name(&peripheral.name));
+        for register_proc in register_procs {
+            let _ = writeln!(script, "    {}_show", register_proc);
+        }
+        script.push_str("}\n");
+    }
+
+    script
+}
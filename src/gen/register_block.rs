@@ -0,0 +1,279 @@
+//! The gen::register_block module generates the `#[repr(C)]` RegisterBlock structs of
+//! peripherals and clusters.
+//!
+//! A register block is made of registers and clusters (nested register blocks, optionally
+//! repeated as arrays). Its members are laid out by offset, with reserved fields filling the
+//! gaps so every member ends up at the offset from the description.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use super::ident::snake_ident;
+use super::naming;
+use super::utils::calculate_reserved_region;
+use crate::{
+    config::{Config, Naming},
+    parse::{utils::Access, Cluster, Register},
+};
+
+/// A member of a register block, either a register or a cluster.
+enum BlockMember<'a> {
+    Register(&'a Register),
+    Cluster(&'a Cluster),
+}
+
+impl BlockMember<'_> {
+    /// The offset of the member from the start of the register block.
+    fn offset(&self) -> usize {
+        match self {
+            BlockMember::Register(register) => register.offset,
+            BlockMember::Cluster(cluster) => cluster.offset,
+        }
+    }
+
+    /// The size of the member in bytes.
+    fn size(&self) -> usize {
+        match self {
+            BlockMember::Register(register) => register.size / 8,
+            BlockMember::Cluster(cluster) => cluster.element_size() * cluster.count.unwrap_or(1),
+        }
+    }
+}
+
+/// Collects the members of a register block, sorted by offset.
+fn block_members<'a>(registers: &'a [Register], clusters: &'a [Cluster]) -> Vec<BlockMember<'a>> {
+    let mut members: Vec<BlockMember> = registers
+        .iter()
+        .map(BlockMember::Register)
+        .chain(clusters.iter().map(BlockMember::Cluster))
+        .collect();
+    members.sort_by_key(|member| member.offset());
+    members
+}
+
+/// Generates the fields of a register block struct.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+/// padded_size: Option<usize> - The size to pad the block to with a trailing reserved field.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the fields of the struct.
+pub fn gen_register_block_fields(
+    registers: &[Register],
+    clusters: &[Cluster],
+    padded_size: Option<usize>,
+    naming: &Naming,
+) -> TokenStream {
+    let mut register_block_fields = quote! {};
+    let mut previous_offset = 0;
+    let members = block_members(registers, clusters);
+
+    for (i, member) in members.iter().enumerate() {
+        let offset = member.offset();
+
+        // Add reserved fields if there's a gap
+        register_block_fields.extend(gen_reserved_field(i, previous_offset, offset));
+
+        let field = match member {
+            BlockMember::Register(register) => gen_register_field(register, naming),
+            BlockMember::Cluster(cluster) => gen_cluster_field(cluster),
+        };
+        register_block_fields.extend(field);
+
+        previous_offset = offset + member.size();
+    }
+
+    if let Some(padded_size) = padded_size {
+        register_block_fields.extend(gen_reserved_field(
+            members.len(),
+            previous_offset,
+            padded_size,
+        ));
+    }
+
+    register_block_fields
+}
+
+/// Generates a reserved field that fills the gap between two offsets, if there is one.
+/// Gaps are filled with 32-bit words when possible, and bytes otherwise.
+fn gen_reserved_field(index: usize, previous_offset: usize, offset: usize) -> TokenStream {
+    let reserved_field = format_ident!("_reserved{}", index);
+    let gap = offset.saturating_sub(previous_offset);
+
+    if gap.is_multiple_of(4) {
+        let reserved_count = calculate_reserved_region(previous_offset, offset, None);
+        if reserved_count > 0 {
+            return quote! {
+                pub #reserved_field: [u32; #reserved_count],
+            };
+        }
+        quote! {}
+    } else {
+        quote! {
+            pub #reserved_field: [u8; #gap],
+        }
+    }
+}
+
+/// Generates the register block field of a register.
+fn gen_register_field(register: &Register, naming: &Naming) -> TokenStream {
+    let reg_name = snake_ident(&register.name);
+    let reg_type = gen_register_type(register, naming);
+
+    quote! {
+        pub #reg_name: #reg_type,
+    }
+}
+
+/// Generates the type of a register in a register block: the register struct of its
+/// module if it has fields, or a plain volatile wrapper otherwise.
+fn gen_register_type(register: &Register, naming: &Naming) -> TokenStream {
+    if register.fields.is_some() {
+        let reg_module_name = snake_ident(&register.name);
+        let reg_struct_name = naming::register_type(&register.name, naming);
+        quote! { #reg_module_name::#reg_struct_name }
+    } else {
+        match &register.access {
+            Access::ReadOnly => quote! { RO<u32> },
+            Access::WriteOnly => quote! { WO<u32> },
+            Access::ReadWrite | Access::WriteToClear => quote! { RW<u32> },
+        }
+    }
+}
+
+/// Generates the register block field of a cluster, an array for repeated clusters.
+fn gen_cluster_field(cluster: &Cluster) -> TokenStream {
+    let cluster_name = snake_ident(&cluster.name);
+    let doc = cluster.description.clone().unwrap_or_default();
+
+    match cluster.count {
+        Some(count) => quote! {
+            #[doc = #doc]
+            pub #cluster_name: [#cluster_name::RegisterBlock; #count],
+        },
+        None => quote! {
+            #[doc = #doc]
+            pub #cluster_name: #cluster_name::RegisterBlock,
+        },
+    }
+}
+
+/// Generates svd2rust-style accessor methods for the members of a register block.
+/// Cluster arrays get an indexed accessor and an iterator over their elements.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing an `impl RegisterBlock` block with the accessors.
+pub fn gen_register_block_accessors(
+    registers: &[Register],
+    clusters: &[Cluster],
+    naming: &Naming,
+) -> TokenStream {
+    let register_accessors = registers.iter().map(|register| {
+        let reg_name = snake_ident(&register.name);
+        let reg_type = gen_register_type(register, naming);
+        let doc = register.description.clone();
+
+        quote! {
+            #[doc = #doc]
+            #[inline(always)]
+            pub fn #reg_name(&self) -> &#reg_type {
+                &self.#reg_name
+            }
+        }
+    });
+
+    let cluster_accessors = clusters.iter().map(|cluster| {
+        let cluster_name = snake_ident(&cluster.name);
+        let doc = cluster.description.clone().unwrap_or_default();
+
+        if cluster.count.is_some() {
+            let iter_name = naming::cluster_iter(&cluster.name);
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub fn #cluster_name(&self, n: usize) -> &#cluster_name::RegisterBlock {
+                    &self.#cluster_name[n]
+                }
+
+                #[doc = #doc]
+                #[inline(always)]
+                pub fn #iter_name(&self) -> impl Iterator<Item = &#cluster_name::RegisterBlock> {
+                    self.#cluster_name.iter()
+                }
+            }
+        } else {
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub fn #cluster_name(&self) -> &#cluster_name::RegisterBlock {
+                    &self.#cluster_name
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl RegisterBlock {
+            #(#register_accessors)*
+            #(#cluster_accessors)*
+        }
+    }
+}
+
+/// Generates the items of a cluster module: the imports, the given child modules and
+/// the RegisterBlock of the cluster.
+///
+/// # Arguments
+/// cluster: &Cluster - The cluster to generate the items for.
+/// child_mods: Vec<TokenStream> - The register and cluster module declarations or inline modules.
+/// config: &Config - The configuration file that contains the generation options.
+///
+/// # Returns
+/// A TokenStream representing the cluster module items.
+pub fn gen_cluster_items(
+    cluster: &Cluster,
+    child_mods: Vec<TokenStream>,
+    config: &Config,
+) -> TokenStream {
+    // Cluster arrays are padded to the stride, so consecutive elements land on the right offset
+    let padded_size = cluster.stride.map(|_| cluster.element_size());
+    let register_block_fields = gen_register_block_fields(
+        &cluster.registers,
+        cluster.clusters(),
+        padded_size,
+        &config.naming,
+    );
+    let accessors = if config.svd2rust_api {
+        gen_register_block_accessors(&cluster.registers, cluster.clusters(), &config.naming)
+    } else {
+        quote! {}
+    };
+    let snapshot = if config.zero_dependencies {
+        quote! {}
+    } else {
+        super::serde_impls::gen_snapshot(&cluster.registers, cluster.clusters(), &config.naming)
+    };
+
+    quote! {
+        use super::*;
+
+        #(#child_mods)*
+
+        #[repr(C)]
+        pub struct RegisterBlock {
+            #register_block_fields
+        }
+
+        #accessors
+
+        #snapshot
+    }
+}
@@ -0,0 +1,412 @@
+//! The gen::serde_impls module generates the serde support of the generated crate, behind
+//! its `serde` feature.
+//!
+//! The field enums are (de)serialized as their variant name, the register builders as a
+//! map of their fields, through a mirror struct with one member per field, and every
+//! register block gets a `Snapshot` type that holds the value of each readable register
+//! without read side effects.
+//!
+//! Every impl is a separate item behind `#[cfg(feature = "serde")]` rather than a
+//! `cfg_attr` derive, so the `unexpected_cfgs` lint can be allowed on the enclosing module
+//! when the code is included in a crate that does not declare the feature.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::enum_repr_type;
+use super::ident::{pascal_ident, snake_ident};
+use super::naming;
+use super::utils::{bit_range_mask, get_smallest_rust_type};
+use crate::{
+    config::Naming,
+    parse::{utils::Access, Cluster, Enum, Register},
+};
+
+/// Generates the root items of the serde support, behind the `serde` feature: the
+/// `EnumVariants` trait that (de)serializes the field enums and the `EnumField` type that
+/// (de)serializes the enum fields of the builders.
+///
+/// # Returns
+/// A TokenStream representing the root items.
+pub fn gen_serde_items() -> TokenStream {
+    quote! {
+        /// The variants of a field enum, which are (de)serialized as unit variants, the same
+        /// way the serde derives do.
+        #[cfg(feature = "serde")]
+        #[doc(hidden)]
+        pub trait EnumVariants: Sized + Copy + PartialEq + 'static {
+            /// The name of the enum
+            const NAME: &'static str;
+
+            /// The names of the variants
+            const NAMES: &'static [&'static str];
+
+            /// The variants, in the order of their names
+            const VARIANTS: &'static [Self];
+
+            /// Serializes the variant as a unit variant of the enum
+            fn serialize_variant<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let index = Self::VARIANTS
+                    .iter()
+                    .position(|variant| variant == self)
+                    .unwrap_or_default();
+                serializer.serialize_unit_variant(Self::NAME, index as u32, Self::NAMES[index])
+            }
+
+            /// Deserializes a unit variant of the enum, by name or by index
+            fn deserialize_variant<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct VariantIndex<E>(core::marker::PhantomData<E>);
+
+                impl<'de, E: EnumVariants> serde::de::Visitor<'de> for VariantIndex<E> {
+                    type Value = usize;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        f.write_str("a variant of ")?;
+                        f.write_str(E::NAME)
+                    }
+
+                    fn visit_u64<Er: serde::de::Error>(self, value: u64) -> Result<usize, Er> {
+                        match usize::try_from(value) {
+                            Ok(index) if index < E::NAMES.len() => Ok(index),
+                            _ => Err(Er::invalid_value(serde::de::Unexpected::Unsigned(value), &self)),
+                        }
+                    }
+
+                    fn visit_str<Er: serde::de::Error>(self, value: &str) -> Result<usize, Er> {
+                        E::NAMES
+                            .iter()
+                            .position(|name| *name == value)
+                            .ok_or_else(|| Er::unknown_variant(value, E::NAMES))
+                    }
+                }
+
+                impl<'de, E: EnumVariants> serde::de::DeserializeSeed<'de> for VariantIndex<E> {
+                    type Value = usize;
+
+                    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+                        deserializer.deserialize_identifier(self)
+                    }
+                }
+
+                struct EnumVisitor<E>(core::marker::PhantomData<E>);
+
+                impl<'de, E: EnumVariants> serde::de::Visitor<'de> for EnumVisitor<E> {
+                    type Value = E;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        f.write_str("enum ")?;
+                        f.write_str(E::NAME)
+                    }
+
+                    fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<E, A::Error> {
+                        let (index, variant) = data.variant_seed(VariantIndex::<E>(core::marker::PhantomData))?;
+                        serde::de::VariantAccess::unit_variant(variant)?;
+                        Ok(E::VARIANTS[index])
+                    }
+                }
+
+                deserializer.deserialize_enum(Self::NAME, Self::NAMES, EnumVisitor(core::marker::PhantomData))
+            }
+        }
+
+        /// The bits of an enum field of a builder, as they are serialized: as the variant
+        /// they match, or as a number when they match no variant (e.g. reserved values read
+        /// from the hardware). Both forms are accepted back, which requires a
+        /// self-describing format such as JSON.
+        ///
+        /// E: The enum of the field values
+        /// U: The raw bits type of the field
+        #[cfg(feature = "serde")]
+        #[doc(hidden)]
+        pub struct EnumField<E, U> {
+            bits: U,
+            _enum: core::marker::PhantomData<E>,
+        }
+
+        #[cfg(feature = "serde")]
+        impl<E, U: Copy> EnumField<E, U> {
+            /// Creates the field from its raw bits
+            pub fn new(bits: U) -> Self {
+                Self { bits, _enum: core::marker::PhantomData }
+            }
+
+            /// Returns the raw bits of the field
+            pub fn bits(&self) -> U {
+                self.bits
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<E, U> serde::Serialize for EnumField<E, U>
+        where
+            E: TryFrom<U> + serde::Serialize,
+            U: Copy + serde::Serialize,
+        {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match E::try_from(self.bits) {
+                    Ok(variant) => variant.serialize(serializer),
+                    Err(_) => self.bits.serialize(serializer),
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, E, U> serde::Deserialize<'de> for EnumField<E, U>
+        where
+            E: serde::Deserialize<'de> + Into<U>,
+            U: Copy + TryFrom<u64>,
+        {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct FieldVisitor<E, U>(core::marker::PhantomData<(E, U)>);
+
+                impl<'de, E, U> serde::de::Visitor<'de> for FieldVisitor<E, U>
+                where
+                    E: serde::Deserialize<'de> + Into<U>,
+                    U: Copy + TryFrom<u64>,
+                {
+                    type Value = EnumField<E, U>;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        f.write_str("a variant name or the raw bits of the field")
+                    }
+
+                    fn visit_str<Er: serde::de::Error>(self, value: &str) -> Result<Self::Value, Er> {
+                        let deserializer = serde::de::IntoDeserializer::<Er>::into_deserializer(value);
+                        let variant = E::deserialize(deserializer)?;
+                        Ok(EnumField::new(variant.into()))
+                    }
+
+                    fn visit_u64<Er: serde::de::Error>(self, value: u64) -> Result<Self::Value, Er> {
+                        U::try_from(value).map(EnumField::new).map_err(|_| {
+                            Er::invalid_value(serde::de::Unexpected::Unsigned(value), &self)
+                        })
+                    }
+
+                    fn visit_i64<Er: serde::de::Error>(self, value: i64) -> Result<Self::Value, Er> {
+                        match u64::try_from(value) {
+                            Ok(value) => self.visit_u64(value),
+                            Err(_) => Err(Er::invalid_value(serde::de::Unexpected::Signed(value), &self)),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_any(FieldVisitor(core::marker::PhantomData))
+            }
+        }
+    }
+}
+
+/// Generates the Serialize and Deserialize impls of a field enum, behind the `serde`
+/// feature. The variants are written as their name.
+///
+/// # Arguments
+/// enum_def: &Enum - The enum to generate the impls for.
+///
+/// # Returns
+/// A TokenStream representing the generated impls.
+pub fn gen_enum_serde(enum_def: &Enum) -> TokenStream {
+    let enum_name = pascal_ident(&enum_def.name);
+    let enum_name_str = enum_name.to_string();
+    let variant_names: Vec<_> = enum_def
+        .values
+        .iter()
+        .map(|value| pascal_ident(&value.name))
+        .collect();
+    let variant_name_strs = variant_names.iter().map(|name| name.to_string());
+
+    quote! {
+        #[cfg(feature = "serde")]
+        impl EnumVariants for #enum_name {
+            const NAME: &'static str = #enum_name_str;
+            const NAMES: &'static [&'static str] = &[#(#variant_name_strs),*];
+            const VARIANTS: &'static [Self] = &[#(Self::#variant_names),*];
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for #enum_name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.serialize_variant(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for #enum_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Self::deserialize_variant(deserializer)
+            }
+        }
+    }
+}
+
+/// Generates the Serialize and Deserialize impls of a register builder, behind the
+/// `serde` feature. Every field is included, whatever its access, and enum fields are
+/// written as their variant, or as a number when their bits match no variant.
+///
+/// # Arguments
+/// register: &Register - The register to generate the impls for. It must have fields.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the mirror struct of the fields and the impls.
+pub fn gen_builder_serde(register: &Register, naming: &Naming) -> TokenStream {
+    let register_builder_name = naming::builder_type(&register.name, naming);
+    let fields_name = naming::fields_type(&register.name);
+    let builder_name_str = register_builder_name.to_string();
+    let register_type = get_smallest_rust_type(register.size);
+    let fields = register.fields.as_ref().unwrap();
+
+    let mut members = Vec::new();
+    let mut to_fields = Vec::new();
+    let mut from_fields = Vec::new();
+
+    for field in fields {
+        let field_name = snake_ident(&field.name);
+        let (start, end) = field.bit_range.get_bit_range();
+        let mask = bit_range_mask(start, end);
+        let raw_bits = quote! { (builder.value & #mask) >> #start };
+
+        if let Some(enum_def) = &field.enums {
+            let enum_name = pascal_ident(&enum_def.name);
+            let enum_type = enum_repr_type(&field.bit_range);
+            members.push(quote! { #field_name: EnumField<#enum_name, #enum_type>, });
+            to_fields.push(quote! {
+                #field_name: EnumField::new((#raw_bits) as #enum_type),
+            });
+            from_fields.push(quote! {
+                value |= (fields.#field_name.bits() as #register_type) << #start & #mask;
+            });
+        } else if field.bit_range.is_single_bit() {
+            members.push(quote! { #field_name: bool, });
+            to_fields.push(quote! { #field_name: builder.value & #mask != 0, });
+            from_fields.push(quote! {
+                value |= (fields.#field_name as #register_type) << #start;
+            });
+        } else {
+            let field_type = get_smallest_rust_type(end - start + 1);
+            members.push(quote! { #field_name: #field_type, });
+            to_fields.push(quote! { #field_name: (#raw_bits) as #field_type, });
+            from_fields.push(quote! {
+                value |= (fields.#field_name as #register_type) << #start & #mask;
+            });
+        }
+    }
+
+    quote! {
+        /// The fields of the builder, as they are serialized
+        #[cfg(feature = "serde")]
+        #[derive(serde::Serialize, serde::Deserialize)]
+        #[serde(rename = #builder_name_str)]
+        struct #fields_name {
+            #(#members)*
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for #register_builder_name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let builder = self;
+                let fields = #fields_name {
+                    #(#to_fields)*
+                };
+                fields.serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for #register_builder_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let fields = #fields_name::deserialize(deserializer)?;
+                let mut value: #register_type = 0;
+                #(#from_fields)*
+                Ok(Self { value })
+            }
+        }
+    }
+}
+
+/// Generates the Snapshot type of a register block, behind the `serde` feature, with a
+/// member for every readable register and cluster, and the `snapshot()` method that
+/// fills it by reading the registers. Registers with read side effects are left out, so
+/// taking a snapshot does not change the state of the peripheral.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the Snapshot struct and the `snapshot()` method.
+pub fn gen_snapshot(registers: &[Register], clusters: &[Cluster], naming: &Naming) -> TokenStream {
+    let mut members = Vec::new();
+    let mut reads = Vec::new();
+
+    for register in registers {
+        if matches!(register.access, Access::WriteOnly)
+            || register.read_side_effects.unwrap_or(false)
+        {
+            continue;
+        }
+
+        let reg_name = snake_ident(&register.name);
+        let doc = register.description.clone();
+        if register.fields.is_some() {
+            let reg_module_name = snake_ident(&register.name);
+            let register_builder_name = naming::builder_type(&register.name, naming);
+            members.push(quote! {
+                #[doc = #doc]
+                pub #reg_name: #reg_module_name::#register_builder_name,
+            });
+            reads.push(quote! { #reg_name: RegisterRO::read(&self.#reg_name), });
+        } else {
+            members.push(quote! {
+                #[doc = #doc]
+                pub #reg_name: u32,
+            });
+            reads.push(quote! { #reg_name: self.#reg_name.read(), });
+        }
+    }
+
+    for cluster in clusters {
+        let cluster_name = snake_ident(&cluster.name);
+        let doc = cluster.description.clone().unwrap_or_default();
+        match cluster.count {
+            Some(count) => {
+                members.push(quote! {
+                    #[doc = #doc]
+                    pub #cluster_name: [#cluster_name::Snapshot; #count],
+                });
+                reads.push(quote! {
+                    #cluster_name: core::array::from_fn(|i| self.#cluster_name[i].snapshot()),
+                });
+            }
+            None => {
+                members.push(quote! {
+                    #[doc = #doc]
+                    pub #cluster_name: #cluster_name::Snapshot,
+                });
+                reads.push(quote! { #cluster_name: self.#cluster_name.snapshot(), });
+            }
+        }
+    }
+
+    quote! {
+        /// The values of every readable register of the block, except the ones with read
+        /// side effects
+        #[cfg(feature = "serde")]
+        #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+        pub struct Snapshot {
+            #(#members)*
+        }
+
+        #[cfg(feature = "serde")]
+        impl RegisterBlock {
+            /// Reads every readable register of the block into a Snapshot
+            ///
+            /// Registers with read side effects (such as FIFOs) are not read.
+            pub fn snapshot(&self) -> Snapshot {
+                Snapshot {
+                    #(#reads)*
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! The gen::svd2rust module generates an svd2rust-compatible API on top of the register
+//! builders: `R` and `W` reader and writer types for every register, with a proxy per field,
+//! and closure-based `read`, `write`, `modify` and `reset` methods on the register.
+//!
+//! This lets drivers written against svd2rust PACs, e.g.
+//! `uart.mode.modify(|r, w| w.char_len().variant(CharacterLength::SixBits))`,
+//! be used with the generated crate. The closure-based methods are implemented through the
+//! traits of the `svd2rust` module of the generated crate rather than as inherent methods,
+//! so they only replace the methods of the builder traits where the drivers import them.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use super::ident::{pascal_ident, snake_ident};
+use super::{enum_repr_type, naming, utils::get_smallest_rust_type};
+use crate::{
+    config::Naming,
+    parse::{utils::Access, Enum, Register},
+};
+
+/// Generates the generic reader, writer and field proxy types shared by all registers.
+/// They are placed next to the register traits in the root of the crate.
+///
+/// # Returns
+/// A TokenStream containing the Reader, Writer, FieldReader, BitReader, FieldWriter and
+/// BitWriter types, and the `svd2rust` module with the traits of the closure-based methods.
+pub fn gen_proxy_types() -> TokenStream {
+    quote! {
+        /// The svd2rust-style closure-based methods of the registers. Import them with
+        /// `use <crate>::svd2rust::*` in the drivers that use them; where they are imported,
+        /// the methods of the RegisterRO, RegisterWO and RegisterRW traits must be called as
+        /// `RegisterRW::modify(&mut reg, |b| ...)`.
+        pub mod svd2rust {
+            /// A register that can be read through its `R` type
+            pub trait Readable {
+                /// The value read from the register
+                type R;
+
+                /// Reads the register
+                fn read(&self) -> Self::R;
+            }
+
+            /// A register that can be written through its `W` type
+            pub trait Writable {
+                /// The value to write to the register
+                type W;
+
+                /// Writes the register, starting from its reset value
+                fn write<F>(&self, f: F)
+                where
+                    F: FnOnce(&mut Self::W) -> &mut Self::W;
+
+                /// Writes the reset value to the register
+                fn reset(&self);
+            }
+
+            /// A register that can be read, changed and written back
+            pub trait Modifiable: Readable + Writable {
+                /// Reads the register, lets the closure change the value and writes it back
+                fn modify<F>(&self, f: F)
+                where
+                    F: for<'w> FnOnce(&Self::R, &'w mut Self::W) -> &'w mut Self::W;
+            }
+        }
+
+        /// The value read from a register, with a reader proxy for every field
+        ///
+        /// U: The raw bits type of the register
+        /// REG: The marker type of the register
+        pub struct Reader<U, REG> {
+            bits: U,
+            _reg: core::marker::PhantomData<REG>,
+        }
+
+        impl<U: Copy, REG> Reader<U, REG> {
+            /// Creates a reader from the raw bits of the register
+            #[inline(always)]
+            pub fn new(bits: U) -> Self {
+                Self { bits, _reg: core::marker::PhantomData }
+            }
+
+            /// Returns the raw bits of the register
+            #[inline(always)]
+            pub fn bits(&self) -> U {
+                self.bits
+            }
+        }
+
+        /// The value to write to a register, with a writer proxy for every field
+        ///
+        /// U: The raw bits type of the register
+        /// REG: The marker type of the register
+        pub struct Writer<U, REG> {
+            bits: U,
+            _reg: core::marker::PhantomData<REG>,
+        }
+
+        impl<U: Copy, REG> Writer<U, REG> {
+            /// Creates a writer from the raw bits of the register
+            #[inline(always)]
+            pub fn new(bits: U) -> Self {
+                Self { bits, _reg: core::marker::PhantomData }
+            }
+
+            /// Sets the raw bits of the register
+            ///
+            /// # Safety
+            /// The bits are written as is, which may set reserved bits or invalid field values
+            #[inline(always)]
+            pub unsafe fn bits(&mut self, bits: U) -> &mut Self {
+                self.bits = bits;
+                self
+            }
+
+            /// Returns the raw bits that will be written to the register
+            #[inline(always)]
+            pub fn into_bits(self) -> U {
+                self.bits
+            }
+        }
+
+        /// The value of a multi-bit field of a register
+        ///
+        /// U: The raw bits type of the field
+        /// FI: The enum of the field values, if there is one
+        #[derive(Debug, Clone, Copy)]
+        pub struct FieldReader<U, FI = ()> {
+            bits: U,
+            _field: core::marker::PhantomData<FI>,
+        }
+
+        impl<U: Copy, FI> FieldReader<U, FI> {
+            /// Creates a field reader from the raw bits of the field
+            #[inline(always)]
+            pub fn new(bits: U) -> Self {
+                Self { bits, _field: core::marker::PhantomData }
+            }
+
+            /// Returns the raw bits of the field
+            #[inline(always)]
+            pub fn bits(&self) -> U {
+                self.bits
+            }
+        }
+
+        impl<U: Copy, FI: TryFrom<U>> FieldReader<U, FI> {
+            /// Returns the value of the field, or None if the bits do not match any variant
+            #[inline(always)]
+            pub fn variant(&self) -> Option<FI> {
+                FI::try_from(self.bits).ok()
+            }
+        }
+
+        impl<U: Copy + PartialEq, FI: Copy + Into<U>> PartialEq<FI> for FieldReader<U, FI> {
+            #[inline(always)]
+            fn eq(&self, other: &FI) -> bool {
+                self.bits == (*other).into()
+            }
+        }
+
+        /// The value of a single-bit field of a register
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct BitReader {
+            bits: bool,
+        }
+
+        impl BitReader {
+            /// Creates a bit reader from the value of the bit
+            #[inline(always)]
+            pub fn new(bits: bool) -> Self {
+                Self { bits }
+            }
+
+            /// Returns the value of the bit
+            #[inline(always)]
+            pub fn bit(&self) -> bool {
+                self.bits
+            }
+
+            /// Returns true if the bit is set
+            #[inline(always)]
+            pub fn bit_is_set(&self) -> bool {
+                self.bits
+            }
+
+            /// Returns true if the bit is clear
+            #[inline(always)]
+            pub fn bit_is_clear(&self) -> bool {
+                !self.bits
+            }
+        }
+
+        /// Writes a multi-bit field of a register
+        ///
+        /// U: The raw bits type of the register
+        /// REG: The marker type of the register
+        /// FV: The raw bits type of the field
+        /// FI: The enum of the field values, if there is one
+        pub struct FieldWriter<'a, U, REG, FV, FI = ()> {
+            w: &'a mut Writer<U, REG>,
+            start: usize,
+            end: usize,
+            _field: core::marker::PhantomData<(FV, FI)>,
+        }
+
+        impl<'a, U: Copy + BitField, REG, FV: Into<U>, FI> FieldWriter<'a, U, REG, FV, FI> {
+            /// Creates a writer for the field in the given (inclusive) bit range
+            #[inline(always)]
+            pub fn new(w: &'a mut Writer<U, REG>, start: usize, end: usize) -> Self {
+                Self { w, start, end, _field: core::marker::PhantomData }
+            }
+
+            /// Sets the raw bits of the field
+            #[inline(always)]
+            pub fn bits(self, value: FV) -> &'a mut Writer<U, REG> {
+                self.w.bits.set_bits(self.start..=self.end, value.into());
+                self.w
+            }
+        }
+
+        impl<'a, U: Copy + BitField, REG, FV: Into<U>, FI: Into<FV>> FieldWriter<'a, U, REG, FV, FI> {
+            /// Sets the field to the given value
+            #[inline(always)]
+            pub fn variant(self, variant: FI) -> &'a mut Writer<U, REG> {
+                self.bits(variant.into())
+            }
+        }
+
+        /// Writes a single-bit field of a register
+        ///
+        /// U: The raw bits type of the register
+        /// REG: The marker type of the register
+        pub struct BitWriter<'a, U, REG> {
+            w: &'a mut Writer<U, REG>,
+            bit: usize,
+        }
+
+        impl<'a, U: Copy + BitField, REG> BitWriter<'a, U, REG> {
+            /// Creates a writer for the given bit
+            #[inline(always)]
+            pub fn new(w: &'a mut Writer<U, REG>, bit: usize) -> Self {
+                Self { w, bit }
+            }
+
+            /// Sets the bit to the given value
+            #[inline(always)]
+            pub fn bit(self, value: bool) -> &'a mut Writer<U, REG> {
+                self.w.bits.set_bit(self.bit, value);
+                self.w
+            }
+
+            /// Sets the bit
+            #[inline(always)]
+            pub fn set_bit(self) -> &'a mut Writer<U, REG> {
+                self.bit(true)
+            }
+
+            /// Clears the bit
+            #[inline(always)]
+            pub fn clear_bit(self) -> &'a mut Writer<U, REG> {
+                self.bit(false)
+            }
+        }
+    }
+}
+
+/// Generates the svd2rust-style API of a register: the marker type, the `R` and `W`
+/// types with a proxy per field, and the impls of the `svd2rust` traits with the `read`,
+/// `write`, `modify` and `reset` methods.
+///
+/// # Arguments
+/// register: &Register - The register to generate the API for. It must have fields.
+/// naming: &Naming - The naming conventions of the generated code.
+///
+/// # Returns
+/// A TokenStream representing the items to add to the register module.
+pub fn gen_register_proxies(register: &Register, naming: &Naming) -> TokenStream {
+    let register_struct_name = naming::register_type(&register.name, naming);
+    let register_builder_name = naming::builder_type(&register.name, naming);
+    let register_spec_name = naming::spec_type(&register.name);
+    let register_type = get_smallest_rust_type(register.size);
+    let fields = register.fields.as_ref().unwrap();
+
+    let reader_doc = format!("The value read from the {} register", register.name);
+    let writer_doc = format!("The value to write to the {} register", register.name);
+    let spec_doc = format!("Marker type of the {} register", register.name);
+
+    let mut aliases = Vec::new();
+    let mut reader_methods = Vec::new();
+    let mut writer_methods = Vec::new();
+    let mut variant_impls = Vec::new();
+
+    for field in fields {
+        let field_name = snake_ident(&field.name);
+        let reader_name = naming::field_reader(&field.name);
+        let writer_name = naming::field_writer(&field.name);
+        let doc = field.description.clone();
+        let (start, end) = field.bit_range.get_bit_range();
+        let field_access = field.access.as_ref().unwrap_or(&Access::ReadWrite);
+        let is_readable = matches!(field_access, Access::ReadOnly | Access::ReadWrite);
+        let is_writable = matches!(
+            field_access,
+            Access::WriteOnly | Access::WriteToClear | Access::ReadWrite
+        );
+
+        match &field.enums {
+            Some(enum_def) => {
+                let enum_name = pascal_ident(&enum_def.name);
+                let enum_type = enum_repr_type(&field.bit_range);
+
+                if is_readable {
+                    aliases.push(quote! {
+                        pub type #reader_name = FieldReader<#enum_type, #enum_name>;
+                    });
+                    reader_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #reader_name {
+                            #reader_name::new(self.bits().get_bits(#start..=#end) as #enum_type)
+                        }
+                    });
+                    variant_impls.push(gen_variant_predicates(&reader_name, enum_def, &enum_type));
+                }
+
+                if is_writable {
+                    aliases.push(quote! {
+                        pub type #writer_name<'a> = FieldWriter<
+                            'a,
+                            #register_type,
+                            #register_spec_name,
+                            #enum_type,
+                            #enum_name,
+                        >;
+                    });
+                    writer_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&mut self) -> #writer_name<'_> {
+                            #writer_name::new(self, #start, #end)
+                        }
+                    });
+                    variant_impls.push(gen_variant_setters(&writer_name, enum_def));
+                }
+            }
+            None if field.bit_range.is_single_bit() => {
+                if is_readable {
+                    aliases.push(quote! {
+                        pub type #reader_name = BitReader;
+                    });
+                    reader_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #reader_name {
+                            #reader_name::new(self.bits().get_bit(#start))
+                        }
+                    });
+                }
+
+                if is_writable {
+                    aliases.push(quote! {
+                        pub type #writer_name<'a> =
+                            BitWriter<'a, #register_type, #register_spec_name>;
+                    });
+                    writer_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&mut self) -> #writer_name<'_> {
+                            #writer_name::new(self, #start)
+                        }
+                    });
+                }
+            }
+            None => {
+                let field_type = get_smallest_rust_type(end - start + 1);
+
+                if is_readable {
+                    aliases.push(quote! {
+                        pub type #reader_name = FieldReader<#field_type>;
+                    });
+                    reader_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #reader_name {
+                            #reader_name::new(self.bits().get_bits(#start..=#end) as #field_type)
+                        }
+                    });
+                }
+
+                if is_writable {
+                    aliases.push(quote! {
+                        pub type #writer_name<'a> =
+                            FieldWriter<'a, #register_type, #register_spec_name, #field_type>;
+                    });
+                    writer_methods.push(quote! {
+                        #[doc = #doc]
+                        #[inline(always)]
+                        pub fn #field_name(&mut self) -> #writer_name<'_> {
+                            #writer_name::new(self, #start, #end)
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    let register_impls =
+        gen_register_impls(register, &register_struct_name, &register_builder_name);
+
+    quote! {
+        #[doc = #spec_doc]
+        pub struct #register_spec_name;
+
+        #[doc = #reader_doc]
+        pub type R = Reader<#register_type, #register_spec_name>;
+
+        #[doc = #writer_doc]
+        pub type W = Writer<#register_type, #register_spec_name>;
+
+        #(#aliases)*
+
+        impl R {
+            #(#reader_methods)*
+        }
+
+        impl W {
+            #(#writer_methods)*
+        }
+
+        #(#variant_impls)*
+
+        #register_impls
+    }
+}
+
+/// Generates the impls of the `svd2rust` traits of a register, depending on its access.
+/// The writes start from the reset value, which is the default value of the builder.
+fn gen_register_impls(
+    register: &Register,
+    register_struct_name: &Ident,
+    register_builder_name: &Ident,
+) -> TokenStream {
+    let read = quote! {
+        impl svd2rust::Readable for #register_struct_name {
+            type R = R;
+
+            #[inline(always)]
+            fn read(&self) -> R {
+                R::new(self.inner.read())
+            }
+        }
+    };
+
+    let write = quote! {
+        impl svd2rust::Writable for #register_struct_name {
+            type W = W;
+
+            #[inline(always)]
+            fn write<F>(&self, f: F)
+            where
+                F: FnOnce(&mut W) -> &mut W,
+            {
+                let mut w = W::new(#register_builder_name::default().into_bits());
+                f(&mut w);
+                unsafe { self.inner.write(w.into_bits()) }
+            }
+
+            #[inline(always)]
+            fn reset(&self) {
+                unsafe { self.inner.write(#register_builder_name::default().into_bits()) }
+            }
+        }
+    };
+
+    let modify = quote! {
+        impl svd2rust::Modifiable for #register_struct_name {
+            #[inline(always)]
+            fn modify<F>(&self, f: F)
+            where
+                F: for<'w> FnOnce(&R, &'w mut W) -> &'w mut W,
+            {
+                let bits = self.inner.read();
+                let r = R::new(bits);
+                let mut w = W::new(bits);
+                f(&r, &mut w);
+                unsafe { self.inner.write(w.into_bits()) }
+            }
+        }
+    };
+
+    match &register.access {
+        Access::ReadOnly => read,
+        Access::WriteOnly => write,
+        Access::ReadWrite | Access::WriteToClear => quote! {
+            #read
+            #write
+            #modify
+        },
+    }
+}
+
+/// Generates the `is_<variant>()` predicates of the reader of an enum field.
+fn gen_variant_predicates(
+    reader_name: &Ident,
+    enum_def: &Enum,
+    enum_type: &TokenStream,
+) -> TokenStream {
+    let enum_name = pascal_ident(&enum_def.name);
+    let predicates = enum_def.values.iter().map(|value| {
+        let variant_name = pascal_ident(&value.name);
+        let predicate_name = naming::reader_predicate(&value.name);
+        let doc = value.description.clone();
+        quote! {
+            #[doc = #doc]
+            #[inline(always)]
+            pub fn #predicate_name(&self) -> bool {
+                self.bits() == #enum_name::#variant_name as #enum_type
+            }
+        }
+    });
+
+    quote! {
+        impl #reader_name {
+            #(#predicates)*
+        }
+    }
+}
+
+/// Generates the per-variant setters of the writer of an enum field.
+fn gen_variant_setters(writer_name: &Ident, enum_def: &Enum) -> TokenStream {
+    let enum_name = pascal_ident(&enum_def.name);
+    let setters = enum_def.values.iter().map(|value| {
+        let variant_name = pascal_ident(&value.name);
+        let setter_name = naming::variant_writer(&value.name);
+        let doc = value.description.clone();
+        quote! {
+            #[doc = #doc]
+            #[inline(always)]
+            pub fn #setter_name(self) -> &'a mut W {
+                self.variant(#enum_name::#variant_name)
+            }
+        }
+    });
+
+    quote! {
+        impl<'a> #writer_name<'a> {
+            #(#setters)*
+        }
+    }
+}
@@ -1,8 +1,4 @@
-use std::{
-    fs::File,
-    io::Write,
-    path::{Path, PathBuf},
-};
+use std::{fs::File, io::Write, path::Path};
 
 use log::debug;
 use proc_macro2::TokenStream;
@@ -12,11 +8,11 @@ use quote::quote;
 /// If the folder already exists, it does nothing.
 ///
 /// # Arguments
-/// folder_path: &PathBuf - The path to the folder to create.
+/// folder_path: &Path - The path to the folder to create.
 ///
 /// # Returns
 /// A Result containing the success or error of the folder creation operation.
-pub fn create_folder(folder_path: &PathBuf) -> std::io::Result<()> {
+pub fn create_folder(folder_path: &Path) -> std::io::Result<()> {
     if !folder_path.exists() {
         std::fs::create_dir_all(folder_path)?;
     }
@@ -26,14 +22,13 @@ pub fn create_folder(folder_path: &PathBuf) -> std::io::Result<()> {
 /// Writes a given string to a given file path
 ///
 /// # Arguments
-/// file_path: &str - The path to the file to write to.
+/// file_path: &Path - The path to the file to write to.
 /// content: &str - The content to write to the file.
 ///
 /// # Returns
 /// A Result containing the success or error of the file write operation.
-pub fn write_to_file_str(file_path: &PathBuf, content: &str) -> std::io::Result<()> {
-    let path = Path::new(file_path);
-    let mut file = File::create(&path)?;
+pub fn write_to_file_str(file_path: &Path, content: &str) -> std::io::Result<()> {
+    let mut file = File::create(file_path)?;
     file.write_all(content.as_bytes())
 }
 
@@ -98,3 +93,36 @@ pub fn get_smallest_rust_type(size: usize) -> TokenStream {
 pub fn usize_to_bool(value: usize) -> bool {
     value != 0
 }
+
+/// Calculates the mask of a bit range, shifted to the position of the range.
+///
+/// # Arguments
+/// start: usize - The first bit of the range.
+/// end: usize - The last bit of the range (inclusive).
+///
+/// # Returns
+/// The mask with the bits from start to end set, as an unsuffixed literal.
+pub fn bit_range_mask(start: usize, end: usize) -> proc_macro2::Literal {
+    let width = end - start + 1;
+    let mask = if width >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << width) - 1
+    };
+    proc_macro2::Literal::u128_unsuffixed(mask << start)
+}
+
+/// Hashes the contents of a file with 64-bit FNV-1a.
+///
+/// The hash is stable across runs and Rust versions, so it can be stored on disk.
+///
+/// # Arguments
+/// contents: &[u8] - The contents to hash.
+///
+/// # Returns
+/// The 64-bit hash of the contents.
+pub fn hash_contents(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
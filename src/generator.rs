@@ -0,0 +1,324 @@
+//! The generator module contains the builder-style library API on top of the
+//! parse and gen modules. The command line interface is a thin wrapper around it.
+
+use std::{path::PathBuf, time::Duration};
+
+use log::{debug, error, info, warn};
+use stringcase::Caser;
+
+use crate::{
+    config::parse_config,
+    error::Error,
+    gen::{self, cache::ModuleCache, ident::find_collisions, manifest, naming, GeneratedFile},
+    parse::{
+        self,
+        validate::{validate, Diagnostic},
+    },
+    watch::Snapshot,
+};
+
+/// The Layout enum represents the shape of the generated code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// A full crate with a Cargo.toml and one file per peripheral and register module.
+    #[default]
+    Crate,
+    /// A single self-contained `<crate_name>.rs` file with inline modules.
+    SingleFile,
+}
+
+/// The Output enum represents where the generated code is written to.
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    /// Write the generated files to the given directory.
+    Directory(PathBuf),
+    /// Write the single generated file to the given path.
+    File(PathBuf),
+    /// Write the crate to the `OUT_DIR` of the running build script.
+    OutDir,
+    /// Do not write anything, only return the generated files.
+    #[default]
+    InMemory,
+}
+
+/// The Generated struct is the result of a generator run.
+#[derive(Debug)]
+pub struct Generated {
+    /// The generated files, with paths relative to the root of the crate.
+    pub files: Vec<GeneratedFile>,
+    /// The directory the files were written to, if any.
+    pub output_dir: Option<PathBuf>,
+    /// Every file the output depends on (entry file, included files and config file).
+    pub source_files: Vec<PathBuf>,
+    /// The problems found in the description. They are logged as warnings, as the
+    /// crate is still generated.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Builder for a single generator run.
+///
+/// Only the entry file is required. If no crate name is given, the name of the
+/// description is used, and if no output is given, the files are kept in memory.
+/// By default a full crate is generated; see `with_single_file` for a single file.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    entry_file: PathBuf,
+    config_file: Option<PathBuf>,
+    crate_name: Option<String>,
+    layout: Layout,
+    output: Output,
+    rerun_if_changed: bool,
+    zero_dependencies: Option<bool>,
+    force: bool,
+}
+
+impl Generator {
+    /// Creates a new generator for the given entry file.
+    pub fn new(entry_file: impl Into<PathBuf>) -> Self {
+        Generator {
+            entry_file: entry_file.into(),
+            config_file: None,
+            crate_name: None,
+            layout: Layout::default(),
+            output: Output::default(),
+            rerun_if_changed: false,
+            zero_dependencies: None,
+            force: false,
+        }
+    }
+
+    /// Sets the configuration file that dictates the versions of the dependencies.
+    pub fn with_config_file(mut self, config_file: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(config_file.into());
+        self
+    }
+
+    /// Sets the name of the generated crate.
+    pub fn with_crate_name(mut self, crate_name: impl Into<String>) -> Self {
+        self.crate_name = Some(crate_name.into());
+        self
+    }
+
+    /// Writes the generated crate to the given directory. It replaces an output file set
+    /// with `with_output_file`, so a full crate is generated again unless `with_single_file`
+    /// is used.
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        // The single file layout came with the output file
+        if matches!(self.output, Output::File(_)) {
+            self.layout = Layout::Crate;
+        }
+        self.output = Output::Directory(output_dir.into());
+        self
+    }
+
+    /// Writes the generated code to a single file at the given path.
+    pub fn with_output_file(mut self, output_file: impl Into<PathBuf>) -> Self {
+        self.layout = Layout::SingleFile;
+        self.output = Output::File(output_file.into());
+        self
+    }
+
+    /// Generates a single `<crate_name>.rs` file instead of a full crate.
+    /// Combined with `with_out_dir`, it can be pulled into a crate with
+    /// `include!(concat!(env!("OUT_DIR"), "/<crate_name>.rs"))`.
+    pub fn with_single_file(mut self) -> Self {
+        self.layout = Layout::SingleFile;
+        self
+    }
+
+    /// Writes the generated crate to the `OUT_DIR` of the running build script.
+    pub fn with_out_dir(mut self) -> Self {
+        self.output = Output::OutDir;
+        self
+    }
+
+    /// Keeps the generated crate in memory without writing anything.
+    pub fn with_in_memory(mut self) -> Self {
+        self.output = Output::InMemory;
+        self
+    }
+
+    /// Prints `cargo:rerun-if-changed` for every source file when generating,
+    /// so a build script only reruns when the description changes.
+    pub fn with_rerun_if_changed(mut self, rerun_if_changed: bool) -> Self {
+        self.rerun_if_changed = rerun_if_changed;
+        self
+    }
+
+    /// Generates a crate without dependencies, overriding the configuration file.
+    pub fn with_zero_dependencies(mut self, zero_dependencies: bool) -> Self {
+        self.zero_dependencies = Some(zero_dependencies);
+        self
+    }
+
+    /// Overwrites and deletes generated files even if they were edited by hand.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Runs the generator.
+    ///
+    /// # Returns
+    /// A Result containing the generated files and the files they were generated from.
+    pub fn generate(&self) -> Result<Generated, Error> {
+        self.generate_cached(&mut ModuleCache::default())
+    }
+
+    /// Runs the generator, only regenerating the peripheral modules whose definitions
+    /// changed since the run that filled the cache.
+    ///
+    /// # Arguments
+    /// cache: &mut ModuleCache - The generated modules of the previous run.
+    ///
+    /// # Returns
+    /// A Result containing the generated files and the files they were generated from.
+    pub fn generate_cached(&self, cache: &mut ModuleCache) -> Result<Generated, Error> {
+        debug!("Entry file: {:?}", self.entry_file);
+
+        let mut config = parse_config(&self.config_file)?;
+        if let Some(zero_dependencies) = self.zero_dependencies {
+            config.zero_dependencies = zero_dependencies;
+        }
+        debug!("Parsed configuration file: {:#?}", config);
+
+        let mut pac = parse::parse_input(&self.entry_file)?;
+        debug!("Parsed peripheral access: {:#?}", pac);
+
+        // Renaming first, so everything after sees the names of the generated code
+        for path in naming::apply(&mut pac, &config.naming) {
+            warn!("The rename of {} matches nothing in the description", path);
+        }
+
+        let diagnostics = validate(&pac);
+        for diagnostic in &diagnostics {
+            warn!("{}", diagnostic);
+        }
+
+        // Requested atomic helpers that cannot be generated would otherwise be left out silently
+        for missing in gen::atomic::find_missing(&pac, &config) {
+            warn!("{}", missing);
+        }
+
+        // Colliding names would generate code that does not compile
+        let mut collisions = naming::find_conflicts(&config.naming);
+        collisions.extend(find_collisions(&pac));
+        if !collisions.is_empty() {
+            return Err(Error::NameCollisions(collisions));
+        }
+
+        let mut source_files = pac.source_files.clone();
+        source_files.extend(self.config_file.clone());
+
+        if self.rerun_if_changed {
+            for source_file in &source_files {
+                println!("cargo:rerun-if-changed={}", source_file.display());
+            }
+        }
+
+        let crate_name = self
+            .crate_name
+            .clone()
+            .unwrap_or_else(|| pac.name.to_snake_case());
+        let mut files = match self.layout {
+            Layout::Crate => gen::generate_pac_files_cached(&crate_name, &pac, &config, cache),
+            Layout::SingleFile => vec![gen::generate_single_file(&crate_name, &pac, &config)],
+        };
+
+        let output_dir = match &self.output {
+            Output::Directory(dir) => Some(dir.clone()),
+            Output::File(path) => {
+                // The single file is written to the exact path that was requested
+                let file_name = path.file_name().map(PathBuf::from).unwrap_or_default();
+                for file in &mut files {
+                    file.path = file_name.clone();
+                }
+                Some(path.parent().map(PathBuf::from).unwrap_or_default())
+            }
+            Output::OutDir => Some(
+                std::env::var_os("OUT_DIR")
+                    .map(PathBuf::from)
+                    .ok_or(Error::MissingOutDir)?,
+            ),
+            Output::InMemory => None,
+        };
+
+        if let Some(output_dir) = &output_dir {
+            debug!("Output directory: {:?}", output_dir);
+            // Only a full crate owns its output directory, a single file may share it with anything
+            match self.layout {
+                Layout::Crate => manifest::write_tracked_files(output_dir, &files, self.force)?,
+                Layout::SingleFile => gen::write_generated_files(output_dir, &files)?,
+            }
+        }
+
+        Ok(Generated {
+            files,
+            output_dir,
+            source_files,
+            diagnostics,
+        })
+    }
+
+    /// Generates the crate, then watches the entry file, the included files and the
+    /// configuration file, and generates it again whenever one of them changes. Only the
+    /// peripheral modules whose definitions changed are regenerated, and only the files
+    /// whose contents changed are written. Errors are logged instead of returned, so the
+    /// description can be fixed while it is being watched.
+    ///
+    /// # Arguments
+    /// interval: Duration - How often the source files are checked for changes.
+    /// on_generated: impl FnMut(&Generated) - Called after every successful run.
+    pub fn watch(&self, interval: Duration, mut on_generated: impl FnMut(&Generated)) -> ! {
+        let mut cache = ModuleCache::default();
+        let mut source_files = vec![self.entry_file.clone()];
+        source_files.extend(self.config_file.clone());
+
+        loop {
+            // Take the snapshot before reading the files, so a change made during the run is not missed
+            let mut snapshot = Snapshot::take(&source_files);
+
+            match self.generate_cached(&mut cache) {
+                Ok(generated) => {
+                    if self.layout == Layout::Crate {
+                        if cache.regenerated.is_empty() {
+                            info!("No peripheral module changed");
+                        } else {
+                            info!("Regenerated modules: {}", cache.regenerated.join(", "));
+                        }
+                    }
+                    if generated.diagnostics.is_empty() {
+                        info!("The description is valid");
+                    } else {
+                        warn!(
+                            "Found {} problem(s) in the description",
+                            generated.diagnostics.len()
+                        );
+                    }
+                    on_generated(&generated);
+
+                    // The included files may have changed with the entry file
+                    for source_file in &generated.source_files {
+                        snapshot.track(source_file);
+                    }
+                    source_files = generated.source_files;
+                }
+                Err(e) => {
+                    error!("Error generating peripheral access crate: {}", e);
+
+                    // The error can be in an included file, which must be watched to see it fixed
+                    source_files = parse::list_source_files(&self.entry_file);
+                    source_files.extend(self.config_file.clone());
+                    for source_file in &source_files {
+                        snapshot.track(source_file);
+                    }
+                }
+            }
+
+            info!("Watching {} file(s) for changes", source_files.len());
+            for path in snapshot.wait_for_change(interval) {
+                info!("Changed: {}", path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! The inspect::dump module decodes a memory dump of peripheral regions against the
+//! description, for the `dump-decode` command.
+//!
+//! A dump is either a raw binary file with the address of its first byte, or the text
+//! output of the OpenOCD `mdb`/`mdh`/`mdw`/`mdd` commands, which carries its own addresses:
+//!
+//! ```text
+//! 0xe0001000: 00000000 00000020 00000000 00000000 00000000 00000000 00000000 0000028b
+//! ```
+//!
+//! Every readable register that is fully covered by the dump is decoded, and the fields
+//! that differ from their reset value are highlighted in the report.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use serde::Serialize;
+
+use super::{decode_register, DecodedRegister, RegisterRef};
+use crate::{
+    error::Error,
+    parse::{utils::Access, PeripheralAccess},
+};
+
+/// The MemoryDump struct holds the bytes of a memory dump by address.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDump {
+    bytes: BTreeMap<usize, u8>,
+    /// Whether the multi-byte values of the target are big endian
+    big_endian: bool,
+}
+
+impl MemoryDump {
+    /// Creates a MemoryDump from the contents of a binary dump.
+    ///
+    /// # Arguments
+    /// data: &[u8] - The contents of the dump.
+    /// start: usize - The address of the first byte of the dump.
+    /// big_endian: bool - Whether the target is big endian.
+    ///
+    /// # Returns
+    /// The MemoryDump.
+    pub fn from_binary(data: &[u8], start: usize, big_endian: bool) -> Self {
+        let bytes = data
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| (start + i, *byte))
+            .collect();
+        MemoryDump { bytes, big_endian }
+    }
+
+    /// Creates a MemoryDump from the text output of the OpenOCD memory display commands.
+    /// The lines that are not memory lines, such as the echoed commands, are ignored.
+    ///
+    /// # Arguments
+    /// text: &str - The text of the dump.
+    /// big_endian: bool - Whether the target is big endian.
+    ///
+    /// # Returns
+    /// The MemoryDump, or None if the text does not contain any memory line.
+    pub fn from_openocd_text(text: &str, big_endian: bool) -> Option<Self> {
+        let mut dump = MemoryDump {
+            bytes: BTreeMap::new(),
+            big_endian,
+        };
+
+        for line in text.lines() {
+            let Some((address, values)) = line.trim().split_once(':') else {
+                continue;
+            };
+            let Some(mut address) = address
+                .strip_prefix("0x")
+                .and_then(|address| usize::from_str_radix(address, 16).ok())
+            else {
+                continue;
+            };
+
+            // The width of the values is given by their number of digits
+            for value in values.split_whitespace() {
+                let size = value.len() / 2;
+                let Some(value) = u64::from_str_radix(value, 16)
+                    .ok()
+                    .filter(|_| matches!(size, 1 | 2 | 4 | 8) && value.len() % 2 == 0)
+                else {
+                    break;
+                };
+                dump.insert(address, value, size);
+                address += size;
+            }
+        }
+
+        (!dump.bytes.is_empty()).then_some(dump)
+    }
+
+    /// Loads a dump file, as OpenOCD text if it contains memory lines, or else as binary.
+    ///
+    /// # Arguments
+    /// path: &Path - The path to the dump file.
+    /// start: Option<usize> - The address of the first byte, required for binary dumps.
+    /// big_endian: bool - Whether the target is big endian.
+    ///
+    /// # Returns
+    /// A Result containing the MemoryDump.
+    pub fn load(path: &Path, start: Option<usize>, big_endian: bool) -> Result<Self, Error> {
+        let data = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+
+        let text_dump = std::str::from_utf8(&data)
+            .ok()
+            .and_then(|text| MemoryDump::from_openocd_text(text, big_endian));
+        match (text_dump, start) {
+            (Some(dump), _) => Ok(dump),
+            (None, Some(start)) => Ok(MemoryDump::from_binary(&data, start, big_endian)),
+            (None, None) => Err(Error::InvalidDump {
+                path: path.to_path_buf(),
+                reason: "a binary dump needs the address of its first byte".to_string(),
+            }),
+        }
+    }
+
+    /// Stores a value of the given size in bytes, in the byte order of the target.
+    fn insert(&mut self, address: usize, value: u64, size: usize) {
+        for i in 0..size {
+            let shift = if self.big_endian { size - 1 - i } else { i } * 8;
+            self.bytes.insert(address + i, (value >> shift) as u8);
+        }
+    }
+
+    /// Reads a value from the dump.
+    ///
+    /// # Arguments
+    /// address: usize - The address of the value.
+    /// size: usize - The size of the value in bits.
+    ///
+    /// # Returns
+    /// The value, or None if the dump does not contain all of its bytes.
+    pub fn read(&self, address: usize, size: usize) -> Option<u64> {
+        let size = size.div_ceil(8);
+        (0..size).try_fold(0, |value, i| {
+            let shift = if self.big_endian { size - 1 - i } else { i } * 8;
+            let byte = *self.bytes.get(&(address + i))?;
+            Some(value | (byte as u64) << shift)
+        })
+    }
+
+    /// Returns the address range of the dump, with an exclusive end.
+    pub fn range(&self) -> Option<(usize, usize)> {
+        let start = *self.bytes.keys().next()?;
+        let end = *self.bytes.keys().next_back()?;
+        Some((start, end + 1))
+    }
+}
+
+/// The DumpReport struct is a memory dump decoded against the description.
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpReport {
+    /// The address of the first byte of the dump
+    pub start: usize,
+    /// The address after the last byte of the dump
+    pub end: usize,
+    /// The decoded registers, sorted by address
+    pub registers: Vec<DecodedRegister>,
+}
+
+/// Decodes every readable register that is fully covered by a memory dump.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// dump: &MemoryDump - The memory dump.
+/// changed_only: bool - Whether to only keep the registers that differ from their reset value.
+///
+/// # Returns
+/// The DumpReport.
+pub fn decode_dump(pac: &PeripheralAccess, dump: &MemoryDump, changed_only: bool) -> DumpReport {
+    let mut registers = Vec::new();
+
+    for peripheral in &pac.peripherals {
+        for flat_register in peripheral.flat_registers() {
+            let register = flat_register.register;
+            if matches!(register.access, Access::WriteOnly) {
+                continue;
+            }
+
+            let register_ref = RegisterRef {
+                path: format!("{}.{}", peripheral.name, flat_register.path),
+                address: peripheral.base_address + flat_register.offset,
+                register,
+            };
+            if let Some(value) = dump.read(register_ref.address, register.size) {
+                let decoded = decode_register(&register_ref, value);
+                if !changed_only || decoded.differs_from_reset() {
+                    registers.push(decoded);
+                }
+            }
+        }
+    }
+
+    registers.sort_by_key(|register| register.address);
+    let (start, end) = dump.range().unwrap_or_default();
+    DumpReport {
+        start,
+        end,
+        registers,
+    }
+}
+
+impl DumpReport {
+    /// Returns the number of registers that differ from their reset value.
+    fn changed_count(&self) -> usize {
+        self.registers
+            .iter()
+            .filter(|register| register.differs_from_reset())
+            .count()
+    }
+
+    /// Formats the report as plain text. The registers and fields that differ from their
+    /// reset value are marked with a `*`, and the reset value is printed next to them.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "Decoded {} registers in 0x{:08X}..0x{:08X}, {} differ from their reset value (marked with *)",
+            self.registers.len(),
+            self.start,
+            self.end,
+            self.changed_count()
+        );
+
+        for register in &self.registers {
+            let marker = if register.differs_from_reset() {
+                "*"
+            } else {
+                " "
+            };
+            let _ = writeln!(
+                text,
+                "\n{} {} @ 0x{:08X} = {}",
+                marker,
+                register.path,
+                register.address,
+                hex(register.value, register.size)
+            );
+            for field in &register.fields {
+                let marker = if field.differs_from_reset { "*" } else { " " };
+                let _ = write!(
+                    text,
+                    "  {} {:<20} {:<7} = {}",
+                    marker,
+                    field.name,
+                    field.bits,
+                    with_variant(field.value, &field.variant)
+                );
+                if field.differs_from_reset {
+                    let _ = write!(
+                        text,
+                        ", reset {}",
+                        with_variant(field.reset.unwrap_or_default(), &field.reset_variant)
+                    );
+                }
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    /// Formats the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Formats the report as Markdown, with a table of fields per register. The values
+    /// that differ from their reset value are in bold.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        markdown.push_str("# Register dump\n\n");
+        let _ = writeln!(
+            markdown,
+            "Decoded {} registers in `0x{:08X}..0x{:08X}`, {} differ from their reset value.",
+            self.registers.len(),
+            self.start,
+            self.end,
+            self.changed_count()
+        );
+
+        for register in &self.registers {
+            let _ = writeln!(markdown, "\n## {}\n", register.path);
+            let _ = writeln!(
+                markdown,
+                "`0x{:08X}` = `{}`{}",
+                register.address,
+                hex(register.value, register.size),
+                if register.differs_from_reset() {
+                    " (differs from reset)"
+                } else {
+                    ""
+                }
+            );
+            if register.fields.is_empty() {
+                continue;
+            }
+
+            markdown.push_str("\n| Field | Bits | Value | Reset |\n|---|---|---|---|\n");
+            for field in &register.fields {
+                let value = with_variant(field.value, &field.variant);
+                let value = if field.differs_from_reset {
+                    format!("**{}**", value)
+                } else {
+                    value
+                };
+                let reset = field
+                    .reset
+                    .map(|reset| with_variant(reset, &field.reset_variant))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    markdown,
+                    "| {} | {} | {} | {} |",
+                    field.name, field.bits, value, reset
+                );
+            }
+        }
+
+        markdown
+    }
+}
+
+/// Formats a register value in hexadecimal, padded to the size of the register.
+fn hex(value: u64, size: usize) -> String {
+    format!("0x{:0width$X}", value, width = size / 4)
+}
+
+/// Formats a field value in hexadecimal, followed by the name of its variant if it has one.
+fn with_variant(value: u64, variant: &Option<String>) -> String {
+    match variant {
+        Some(variant) => format!("0x{:X} ({})", value, variant),
+        None => format!("0x{:X}", value),
+    }
+}
@@ -0,0 +1,520 @@
+//! The inspect module works on register values instead of generating code: it decodes
+//! raw values into their fields, and encodes field values back into a register value.
+//! It backs the `decode` and `encode` commands:
+//!
+//! ```text
+//! $ rust-pac-gen decode --entry-file zynq-700.json UART.MODE 0x20
+//! UART.MODE @ 0xE0001004 = 0x00000020
+//!   clk_sel              [0]     = 0x0
+//!   char_len             [2:1]   = 0x0 (EightBits)
+//!   parity               [5:3]   = 0x4 (None)
+//! ```
+
+pub mod dump;
+
+use std::fmt;
+
+use serde::Serialize;
+use stringcase::Caser;
+
+use crate::{
+    error::Error,
+    gen::openocd::bits_label,
+    parse::{utils::try_parse_num_string, Field, PeripheralAccess, Register},
+};
+
+/// The RegisterRef struct is a register found by its path, with its absolute address.
+#[derive(Debug, Clone)]
+pub struct RegisterRef<'a> {
+    /// The path of the register, e.g. "UART.MODE" or "DMA.CH0.CFG"
+    pub path: String,
+    pub address: usize,
+    pub register: &'a Register,
+}
+
+/// The DecodedField struct is the value of a single field of a decoded register.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedField {
+    pub name: String,
+    /// The bit range of the field, e.g. "[2:1]"
+    pub bits: String,
+    /// The raw value of the field, not shifted to its position in the register
+    pub value: u64,
+    /// The name of the enum variant that matches the value, if any
+    pub variant: Option<String>,
+    /// The value of the field after reset, if the description defines one
+    pub reset: Option<u64>,
+    /// The name of the enum variant that matches the reset value, if any
+    pub reset_variant: Option<String>,
+    /// Whether the value differs from the reset value. Always false without a reset value.
+    pub differs_from_reset: bool,
+}
+
+/// The DecodedRegister struct is a register value split into its fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedRegister {
+    pub path: String,
+    pub address: usize,
+    pub size: usize,
+    pub value: u64,
+    pub fields: Vec<DecodedField>,
+}
+
+impl DecodedRegister {
+    /// Returns whether any field of the register differs from its reset value.
+    pub fn differs_from_reset(&self) -> bool {
+        self.fields.iter().any(|field| field.differs_from_reset)
+    }
+}
+
+impl fmt::Display for DecodedRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} @ 0x{:08X} = 0x{:0width$X}",
+            self.path,
+            self.address,
+            self.value,
+            width = self.size / 4
+        )?;
+        for field in &self.fields {
+            write!(
+                f,
+                "  {:<20} {:<7} = 0x{:X}",
+                field.name, field.bits, field.value
+            )?;
+            if let Some(variant) = &field.variant {
+                write!(f, " ({})", variant)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds a register by its path, such as "UART.MODE" or "DMA.CH0.CFG" for a register
+/// of a cluster array. The names are matched case-insensitively.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+/// path: &str - The path of the register, starting with the peripheral name.
+///
+/// # Returns
+/// A Result containing the register and its address, or Error::UnknownRegister.
+pub fn find_register<'a>(pac: &'a PeripheralAccess, path: &str) -> Result<RegisterRef<'a>, Error> {
+    let unknown = || Error::UnknownRegister(path.to_string());
+    let (peripheral_name, register_path) = path.split_once('.').ok_or_else(unknown)?;
+
+    let peripheral = pac
+        .peripherals
+        .iter()
+        .find(|peripheral| peripheral.name.eq_ignore_ascii_case(peripheral_name))
+        .ok_or_else(unknown)?;
+    let flat_register = peripheral
+        .flat_registers()
+        .into_iter()
+        .find(|flat_register| flat_register.path.eq_ignore_ascii_case(register_path))
+        .ok_or_else(unknown)?;
+
+    Ok(RegisterRef {
+        path: format!("{}.{}", peripheral.name, flat_register.path),
+        address: peripheral.base_address + flat_register.offset,
+        register: flat_register.register,
+    })
+}
+
+/// Parses a register value given on the command line.
+///
+/// # Arguments
+/// register: &RegisterRef - The register the value belongs to.
+/// value: &str - The value, in decimal, 0x hexadecimal or 0b binary.
+///
+/// # Returns
+/// A Result containing the value, or Error::InvalidValue if it is not a number or does
+/// not fit in the register.
+pub fn parse_register_value(register: &RegisterRef, value: &str) -> Result<u64, Error> {
+    let size = register.register.size;
+    try_parse_num_string(value)
+        .filter(|value| size >= 64 || value >> size == 0)
+        .ok_or_else(|| Error::InvalidValue {
+            field: register.path.clone(),
+            value: value.to_string(),
+        })
+}
+
+/// Decodes a register value into its fields.
+///
+/// # Arguments
+/// register: &RegisterRef - The register the value was read from.
+/// value: u64 - The value of the register.
+///
+/// # Returns
+/// The DecodedRegister, with the fields in the order of the description.
+pub fn decode_register(register: &RegisterRef, value: u64) -> DecodedRegister {
+    let fields = register
+        .register
+        .fields
+        .iter()
+        .flatten()
+        .map(|field| {
+            let field_value = field.extract(value);
+            let reset = register.register.field_reset(field);
+            DecodedField {
+                name: field.name.clone(),
+                bits: bits_label(field),
+                value: field_value,
+                variant: variant_name(field, field_value),
+                reset,
+                reset_variant: reset.and_then(|reset| variant_name(field, reset)),
+                differs_from_reset: reset.is_some_and(|reset| reset != field_value),
+            }
+        })
+        .collect();
+
+    DecodedRegister {
+        path: register.path.clone(),
+        address: register.address,
+        size: register.register.size,
+        value,
+        fields,
+    }
+}
+
+/// Returns the name of the enum variant of a field that matches a value, if any.
+fn variant_name(field: &Field, value: u64) -> Option<String> {
+    field.enums.as_ref().and_then(|enum_def| {
+        enum_def
+            .values
+            .iter()
+            .find(|v| v.value as u64 == value)
+            .map(|v| v.name.clone())
+    })
+}
+
+/// Encodes field values into a register value, starting from the reset value of the
+/// register, so the fields that are not given keep their default value.
+///
+/// # Arguments
+/// register: &RegisterRef - The register to encode the value of.
+/// assignments: &[(&str, &str)] - The field names and their values. A value is a variant
+/// name, a number (decimal, 0x hexadecimal or 0b binary), or `true`/`false` for single bits.
+///
+/// # Returns
+/// A Result containing the register value, or an error for an unknown field or a value
+/// that does not fit in its field.
+pub fn encode_register(register: &RegisterRef, assignments: &[(&str, &str)]) -> Result<u64, Error> {
+    let mut value = register.register.reset_value();
+
+    for (field_name, field_value) in assignments {
+        let field = register
+            .register
+            .fields
+            .iter()
+            .flatten()
+            .find(|field| {
+                field.name.eq_ignore_ascii_case(field_name)
+                    || field.name.to_snake_case() == field_name.to_snake_case()
+            })
+            .ok_or_else(|| Error::UnknownField {
+                register: register.path.clone(),
+                field: field_name.to_string(),
+            })?;
+
+        let bits = parse_field_value(field, field_value).ok_or_else(|| Error::InvalidValue {
+            field: format!("{}.{}", register.path, field.name),
+            value: field_value.to_string(),
+        })?;
+        value = field.insert(value, bits);
+    }
+
+    Ok(value)
+}
+
+/// Parses the value of a field given on the command line.
+///
+/// # Returns
+/// The raw value of the field, or None if it is invalid or does not fit in the field.
+fn parse_field_value(field: &Field, value: &str) -> Option<u64> {
+    let variant = field.enums.as_ref().and_then(|enum_def| {
+        enum_def
+            .values
+            .iter()
+            .find(|v| {
+                v.name.eq_ignore_ascii_case(value)
+                    || v.name.to_pascal_case() == value.to_pascal_case()
+            })
+            .map(|v| v.value as u64)
+    });
+
+    let bits = match (variant, value) {
+        (Some(bits), _) => bits,
+        (None, "true") if field.bit_range.is_single_bit() => 1,
+        (None, "false") if field.bit_range.is_single_bit() => 0,
+        (None, _) => try_parse_num_string(value)?,
+    };
+
+    let max = field.extract(u64::MAX);
+    (bits <= max).then_some(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A description with a UART peripheral, whose MODE register has a single-bit field,
+    /// an enum field with a default variant and a numeric field with a default value, and
+    /// whose BAUD_GEN register declares its reset value, and a DMA peripheral with a
+    /// cluster array.
+    fn description() -> PeripheralAccess {
+        serde_json::from_value(json!({
+            "name": "Test",
+            "register_size": 32,
+            "peripherals": [
+                {
+                    "name": "UART",
+                    "base_address": "0xE0001000",
+                    "registers": [{
+                        "name": "MODE",
+                        "access": "read-write",
+                        "description": "Mode",
+                        "offset": "0x4",
+                        "size": 32,
+                        "fields": [
+                            { "name": "clk_sel", "bit_range": "0", "description": "" },
+                            {
+                                "name": "char_len",
+                                "bit_range": "[2:1]",
+                                "description": "",
+                                "enums": {
+                                    "name": "CharacterLength",
+                                    "values": [
+                                        { "name": "EightBits", "value": "0", "description": "" },
+                                        { "name": "SevenBits", "value": "2", "description": "", "is_default": true },
+                                        { "name": "SixBits", "value": "3", "description": "" }
+                                    ]
+                                }
+                            },
+                            { "name": "level", "bit_range": "[7:4]", "description": "", "default_value": 5 }
+                        ]
+                    }, {
+                        "name": "BAUD_GEN",
+                        "access": "read-write",
+                        "description": "Baud rate generator",
+                        "offset": "0x18",
+                        "size": 32,
+                        "reset_value": "0x28B",
+                        "fields": [
+                            { "name": "cd", "bit_range": "[15:0]", "description": "" }
+                        ]
+                    }]
+                },
+                {
+                    "name": "DMA",
+                    "base_address": "0x40000000",
+                    "registers": [],
+                    "clusters": [{
+                        "name": "CH",
+                        "offset": "0x10",
+                        "count": 2,
+                        "stride": "0x20",
+                        "registers": [{
+                            "name": "CFG",
+                            "access": "read-write",
+                            "description": "Config",
+                            "offset": "0x8",
+                            "size": 32
+                        }]
+                    }]
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn registers_are_found_case_insensitively() {
+        let pac = description();
+        let register = find_register(&pac, "uart.mode").unwrap();
+        assert_eq!(register.path, "UART.MODE");
+        assert_eq!(register.address, 0xE000_1004);
+
+        let register = find_register(&pac, "DMA.CH1.CFG").unwrap();
+        assert_eq!(register.address, 0x4000_0000 + 0x10 + 0x20 + 0x8);
+
+        assert!(matches!(
+            find_register(&pac, "UART.NOPE"),
+            Err(Error::UnknownRegister(_))
+        ));
+        assert!(matches!(
+            find_register(&pac, "UART"),
+            Err(Error::UnknownRegister(_))
+        ));
+    }
+
+    #[test]
+    fn decode_splits_the_value_into_fields() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+        let decoded = decode_register(&register, 0x37);
+
+        let values: Vec<_> = decoded
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.value, field.variant.as_deref()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("clk_sel", 1, None),
+                ("char_len", 3, Some("SixBits")),
+                ("level", 3, None)
+            ]
+        );
+        assert!(decoded.differs_from_reset());
+    }
+
+    #[test]
+    fn decode_reports_values_without_a_variant() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+        let decoded = decode_register(&register, 0x2);
+
+        assert_eq!(decoded.fields[1].value, 1);
+        assert_eq!(decoded.fields[1].variant, None);
+        assert_eq!(
+            decoded.fields[1].reset_variant.as_deref(),
+            Some("SevenBits")
+        );
+    }
+
+    #[test]
+    fn decode_compares_with_the_declared_reset_value() {
+        let pac = description();
+        let register = find_register(&pac, "UART.BAUD_GEN").unwrap();
+
+        let decoded = decode_register(&register, 0x28B);
+        assert_eq!(decoded.fields[0].reset, Some(0x28B));
+        assert!(!decoded.differs_from_reset());
+        assert!(decode_register(&register, 0x10).differs_from_reset());
+    }
+
+    #[test]
+    fn dumps_only_report_the_registers_that_differ_from_reset() {
+        let pac = description();
+        // MODE at its reset value, BAUD_GEN changed from its declared reset value
+        let mut data = vec![0; 0x20];
+        data[0x4..0x8].copy_from_slice(&0x54u32.to_le_bytes());
+        data[0x18..0x1C].copy_from_slice(&0x10u32.to_le_bytes());
+        let dump = dump::MemoryDump::from_binary(&data, 0xE000_1000, false);
+
+        let report = dump::decode_dump(&pac, &dump, true);
+        let paths: Vec<_> = report.registers.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["UART.BAUD_GEN"]);
+
+        data[0x18..0x1C].copy_from_slice(&0x28Bu32.to_le_bytes());
+        let dump = dump::MemoryDump::from_binary(&data, 0xE000_1000, false);
+        assert!(dump::decode_dump(&pac, &dump, true).registers.is_empty());
+    }
+
+    #[test]
+    fn encode_starts_from_the_reset_value() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+        let reset = encode_register(&register, &[]).unwrap();
+
+        assert_eq!(reset, 0x54);
+        assert_eq!(reset, register.register.reset_value());
+        assert!(!decode_register(&register, reset).differs_from_reset());
+    }
+
+    #[test]
+    fn encode_starts_from_the_declared_reset_value() {
+        let pac = description();
+        let register = find_register(&pac, "UART.BAUD_GEN").unwrap();
+
+        assert_eq!(encode_register(&register, &[]).unwrap(), 0x28B);
+        assert_eq!(encode_register(&register, &[("cd", "0x10")]).unwrap(), 0x10);
+    }
+
+    #[test]
+    fn encode_accepts_variants_numbers_and_booleans() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+        let value = encode_register(
+            &register,
+            &[
+                ("clk_sel", "true"),
+                ("CharLen", "six_bits"),
+                ("level", "0xA"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(value, 0xA7);
+    }
+
+    #[test]
+    fn encode_rejects_unknown_fields_and_values_that_do_not_fit() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+
+        assert!(matches!(
+            encode_register(&register, &[("parity", "0")]),
+            Err(Error::UnknownField { .. })
+        ));
+        assert!(matches!(
+            encode_register(&register, &[("level", "16")]),
+            Err(Error::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            encode_register(&register, &[("char_len", "Nine")]),
+            Err(Error::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            encode_register(&register, &[("level", "true")]),
+            Err(Error::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn decode_and_encode_round_trip() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+
+        for value in [0x0, 0x54, 0x37, 0xA7, 0xF2] {
+            let decoded = decode_register(&register, value);
+            let assignments: Vec<(String, String)> = decoded
+                .fields
+                .iter()
+                .map(|field| {
+                    let field_value = field
+                        .variant
+                        .clone()
+                        .unwrap_or_else(|| field.value.to_string());
+                    (field.name.clone(), field_value)
+                })
+                .collect();
+            let assignments: Vec<(&str, &str)> = assignments
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect();
+
+            assert_eq!(encode_register(&register, &assignments).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn register_values_must_fit_the_register() {
+        let pac = description();
+        let register = find_register(&pac, "UART.MODE").unwrap();
+
+        assert_eq!(parse_register_value(&register, "0b101").unwrap(), 5);
+        assert_eq!(
+            parse_register_value(&register, "0xFFFFFFFF").unwrap(),
+            0xFFFF_FFFF
+        );
+        assert!(parse_register_value(&register, "0x100000000").is_err());
+        assert!(parse_register_value(&register, "mode").is_err());
+    }
+}
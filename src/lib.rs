@@ -0,0 +1,50 @@
+//! Rust Peripheral Access Crate Generator
+//!
+//! Generates peripheral access crates from JSON register descriptions. The generator can be
+//! used from the command line, or as a library from a `build.rs` through the [Generator] builder,
+//! here writing a single `zynq_pac.rs` file to `OUT_DIR`:
+//!
+//! ```no_run
+//! use rust_pac_gen::Generator;
+//!
+//! Generator::new("device/zynq-7000.json")
+//!     .with_crate_name("zynq_pac")
+//!     .with_single_file()
+//!     .with_out_dir()
+//!     .with_rerun_if_changed(true)
+//!     .generate()
+//!     .unwrap();
+//! ```
+//!
+//! The crate then pulls the generated modules in with:
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/zynq_pac.rs"));
+//! ```
+
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod gen;
+pub mod generator;
+pub mod inspect;
+pub mod parse;
+pub mod watch;
+
+pub use error::Error;
+pub use generator::{Generated, Generator, Layout, Output};
+
+/// Constants for default values for the versions for the crates
+pub mod defaults {
+    pub const BIT_FIELD_VERSION: &str = "0.10.2";
+    pub const VOLATILE_REGISTER_VERSION: &str = "0.2.2";
+    pub const CORTEX_M_VERSION: &str = "0.7.7";
+    pub const CRITICAL_SECTION_VERSION: &str = "1.1.2";
+    pub const DEFMT_VERSION: &str = "1.0.1";
+    pub const SERDE_VERSION: &str = "1.0.215";
+    pub const LIBC_VERSION: &str = "0.2.172";
+
+    /// The version and edition of the generated crate
+    pub const CRATE_VERSION: &str = "0.1.0";
+    pub const CRATE_EDITION: &str = "2021";
+}
@@ -1,38 +1,285 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use clap::Parser;
-use config::parse_config;
-use log::{debug, info};
-
-pub mod config;
-pub mod gen;
-pub mod parse;
-
-/// Constants for default values for the versions for the crates
-pub mod defaults {
-    pub const BIT_FIELD_VERSION: &str = "0.10.2";
-    pub const VOLATILE_REGISTER_VERSION: &str = "0.2.2";
-}
+use clap::{Parser, Subcommand, ValueEnum};
+use log::info;
+use rust_pac_gen::{
+    diff::diff,
+    gen::{
+        docs::{gen_docs, DocsFormat},
+        write_generated_files,
+    },
+    inspect::{self, dump::MemoryDump},
+    parse::{parse_input, utils::try_parse_num_string},
+    Error, Generator,
+};
 
 #[derive(Parser)]
-#[command(version, about = "Rust Peripheral Access Crate Generator")]
+#[command(
+    version,
+    about = "Rust Peripheral Access Crate Generator",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct CliArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The main entry file that contains the included or defined peripherals
-    #[clap(short, long)]
-    entry_file: PathBuf,
+    #[clap(short, long, required = true)]
+    entry_file: Option<PathBuf>,
 
     /// The output directory where the generated crate will be saved
-    #[clap(short, long)]
-    output_dir: PathBuf,
+    #[clap(short, long, required_unless_present = "single_file")]
+    output_dir: Option<PathBuf>,
 
     /// The name of the generated crate. It should follow the naming conventions of a Rust crate and match the name of the output directory typically.
-    #[clap(short, long)]
-    crate_name: String,
+    #[clap(short, long, required_unless_present = "single_file")]
+    crate_name: Option<String>,
+
+    /// Generate a single self-contained .rs file with inline modules instead of a crate.
+    /// The file is written to the given path, or to stdout if no path (or "-") is given.
+    #[clap(long, num_args = 0..=1, default_missing_value = "-")]
+    single_file: Option<PathBuf>,
 
     /// The configuration file that dictates the versions of the dependencies that is used by the generated crate.
     /// This is optional and if not provided, the latest versions of the dependencies will be used.
     #[clap(long)]
     config_file: Option<PathBuf>,
+
+    /// Generate a crate without any dependencies, with the volatile register access and bit
+    /// manipulation code inlined. Overrides `zero_dependencies` in the configuration file.
+    #[clap(long)]
+    zero_dependencies: bool,
+
+    /// Overwrite and delete generated files even if they were edited by hand since the last generation.
+    #[clap(long)]
+    force: bool,
+
+    /// Keep running and regenerate whenever the entry file, an included file or the
+    /// configuration file changes. Only the modules of the changed peripherals are regenerated.
+    #[clap(long)]
+    watch: bool,
+}
+
+/// The commands that work on the description instead of generating a crate.
+/// Without a command, the crate is generated from the arguments above.
+#[derive(Subcommand)]
+enum Command {
+    /// Decodes a register value into its fields, e.g. `decode -e zynq-700.json UART.MODE 0x20`
+    Decode {
+        /// The main entry file that contains the included or defined peripherals
+        #[clap(short, long)]
+        entry_file: PathBuf,
+
+        /// The register, as PERIPHERAL.REGISTER (e.g. UART.MODE, or DMA.CH0.CFG in a cluster array)
+        register: String,
+
+        /// The value of the register, in decimal, 0x hexadecimal or 0b binary
+        value: String,
+    },
+    /// Encodes field values into a register value, starting from its reset value,
+    /// e.g. `encode -e zynq-700.json UART.MODE char_len=SixBits parity=None`
+    Encode {
+        /// The main entry file that contains the included or defined peripherals
+        #[clap(short, long)]
+        entry_file: PathBuf,
+
+        /// The register, as PERIPHERAL.REGISTER (e.g. UART.MODE, or DMA.CH0.CFG in a cluster array)
+        register: String,
+
+        /// The fields to set, as FIELD=VALUE where VALUE is a variant name, a number or true/false
+        fields: Vec<String>,
+    },
+    /// Decodes a memory dump (a binary file, or the output of the OpenOCD mdw command)
+    /// against every register of the description
+    DumpDecode {
+        /// The main entry file that contains the included or defined peripherals
+        #[clap(short, long)]
+        entry_file: PathBuf,
+
+        /// The dump file
+        dump_file: PathBuf,
+
+        /// The address of the first byte of a binary dump. OpenOCD text dumps contain their addresses.
+        #[clap(long)]
+        start: Option<String>,
+
+        /// The format of the report
+        #[clap(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+
+        /// Read the dump as big endian values
+        #[clap(long)]
+        big_endian: bool,
+
+        /// Only report the registers that differ from their reset value
+        #[clap(long)]
+        changed_only: bool,
+    },
+    /// Generates a register reference (address map, register and field tables, bit-field
+    /// diagrams) as static Markdown or HTML pages
+    Docs {
+        /// The main entry file that contains the included or defined peripherals
+        #[clap(short, long)]
+        entry_file: PathBuf,
+
+        /// The output directory where the pages will be saved
+        #[clap(short, long)]
+        output_dir: PathBuf,
+
+        /// The format of the pages
+        #[clap(long, value_enum, default_value_t = DocsOutput::Html)]
+        format: DocsOutput,
+    },
+    /// Compares two versions of a description and reports whether the changes break the
+    /// API of the generated crate, with the suggested semver bump
+    Diff {
+        /// The previous version of the entry file
+        old: PathBuf,
+
+        /// The new version of the entry file
+        new: PathBuf,
+
+        /// The current version of the generated crate, to print the suggested next version
+        #[clap(long)]
+        current_version: Option<String>,
+
+        /// Print the report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// The output formats of the register reference.
+#[derive(Clone, Copy, ValueEnum)]
+enum DocsOutput {
+    Markdown,
+    Html,
+}
+
+/// The output formats of the dump-decode report.
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+/// Runs one of the commands and prints its result to stdout.
+///
+/// # Arguments
+/// command: Command - The command to run.
+///
+/// # Returns
+/// A Result indicating whether the command succeeded.
+fn run_command(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Decode {
+            entry_file,
+            register,
+            value,
+        } => {
+            let pac = parse_input(&entry_file)?;
+            let register = inspect::find_register(&pac, &register)?;
+            let value = inspect::parse_register_value(&register, &value)?;
+            print!("{}", inspect::decode_register(&register, value));
+        }
+        Command::Encode {
+            entry_file,
+            register,
+            fields,
+        } => {
+            let pac = parse_input(&entry_file)?;
+            let register = inspect::find_register(&pac, &register)?;
+            let assignments = fields
+                .iter()
+                .map(|field| {
+                    field.split_once('=').ok_or_else(|| Error::InvalidValue {
+                        field: register.path.clone(),
+                        value: field.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let value = inspect::encode_register(&register, &assignments)?;
+            print!("{}", inspect::decode_register(&register, value));
+        }
+        Command::DumpDecode {
+            entry_file,
+            dump_file,
+            start,
+            format,
+            big_endian,
+            changed_only,
+        } => {
+            let pac = parse_input(&entry_file)?;
+            let start = start
+                .map(|start| {
+                    try_parse_num_string(&start)
+                        .map(|start| start as usize)
+                        .ok_or_else(|| Error::InvalidValue {
+                            field: "--start".to_string(),
+                            value: start.clone(),
+                        })
+                })
+                .transpose()?;
+            let dump = MemoryDump::load(&dump_file, start, big_endian)?;
+            let report = inspect::dump::decode_dump(&pac, &dump, changed_only);
+            match format {
+                ReportFormat::Text => print!("{}", report.to_text()),
+                ReportFormat::Json => println!("{}", report.to_json()),
+                ReportFormat::Markdown => print!("{}", report.to_markdown()),
+            }
+        }
+        Command::Docs {
+            entry_file,
+            output_dir,
+            format,
+        } => {
+            let pac = parse_input(&entry_file)?;
+            let format = match format {
+                DocsOutput::Markdown => DocsFormat::Markdown,
+                DocsOutput::Html => DocsFormat::Html,
+            };
+            write_generated_files(&output_dir, &gen_docs(&pac, format))?;
+        }
+        Command::Diff {
+            old,
+            new,
+            current_version,
+            json,
+        } => {
+            let report = diff(&parse_input(&old)?, &parse_input(&new)?);
+            let next_version = current_version
+                .map(|current_version| {
+                    report
+                        .suggested_bump()
+                        .apply(&current_version)
+                        .ok_or(Error::InvalidValue {
+                            field: "--current-version".to_string(),
+                            value: current_version,
+                        })
+                })
+                .transpose()?;
+
+            if json {
+                let json = serde_json::json!({
+                    "changes": report.changes,
+                    "suggested_bump": report.suggested_bump(),
+                    "next_version": next_version,
+                });
+                println!("{:#}", json);
+            } else {
+                print!("{}", report);
+                if let Some(next_version) = next_version {
+                    println!("\nSuggested version: {}", next_version);
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Initalizes the logger to be used by the application
@@ -57,29 +304,59 @@ fn main() {
 
     let cli_args = CliArgs::parse();
 
+    if let Some(command) = cli_args.command {
+        if let Err(e) = run_command(command) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     info!("Starting Rust Peripheral Access Crate Generator");
-    debug!("Entry file: {:?}", cli_args.entry_file);
-    debug!("Output directory: {:?}", cli_args.output_dir);
 
-    // Parse out the configuration file
-    let config_file = parse_config(&cli_args.config_file);
-    debug!("Parsed configuration file: {:#?}", config_file);
+    let entry_file = cli_args.entry_file.expect("clap requires an entry file");
+    let mut generator = Generator::new(entry_file);
+    if let Some(crate_name) = cli_args.crate_name {
+        generator = generator.with_crate_name(crate_name);
+    }
+    if let Some(config_file) = cli_args.config_file {
+        generator = generator.with_config_file(config_file);
+    }
+    if cli_args.zero_dependencies {
+        generator = generator.with_zero_dependencies(true);
+    }
+    generator = generator.with_force(cli_args.force);
+
+    let to_stdout = cli_args.single_file.as_deref() == Some(Path::new("-"));
+    generator = match (cli_args.single_file, cli_args.output_dir) {
+        (Some(_), _) if to_stdout => generator.with_single_file().with_in_memory(),
+        (Some(single_file), _) => generator.with_output_file(single_file),
+        (None, Some(output_dir)) => generator.with_output_dir(output_dir),
+        (None, None) => unreachable!("clap requires an output directory or a single file"),
+    };
 
-    let peripheral_access = parse::parse_input(&cli_args.entry_file);
-    debug!("Parsed peripheral access: {:#?}", peripheral_access);
+    if cli_args.watch {
+        generator.watch(Duration::from_millis(500), |generated| {
+            if to_stdout {
+                for file in &generated.files {
+                    print!("{}", file.contents);
+                }
+            }
+        });
+    }
 
     // Generate the peripheral access crate code
-    match peripheral_access {
-        Ok(pac) => {
-            gen::generate_pac_code(
-                &cli_args.output_dir,
-                &cli_args.crate_name,
-                &pac,
-                &config_file,
-            );
+    match generator.generate() {
+        Ok(generated) => {
+            if to_stdout {
+                for file in &generated.files {
+                    print!("{}", file.contents);
+                }
+            }
         }
         Err(e) => {
-            eprintln!("Error parsing input file: {}", e);
+            eprintln!("Error generating peripheral access crate: {}", e);
+            std::process::exit(1);
         }
     }
 }
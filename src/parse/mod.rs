@@ -1,11 +1,18 @@
-use std::path::PathBuf;
+//! The parse module contains logic and data structures for parsing the input JSON file
+//! using the serde library.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-/// The parse module contains logic and data structures for parsing the input JSON file
-/// using the serde library.
 use serde::Deserialize;
 use utils::{Access, BitRange};
 
+use crate::error::Error;
+
 pub mod utils;
+pub mod validate;
 
 /// The Manifest struct represents the top-level definition of the peripheral access crate.
 /// It contains the name of the peripheral access crate, the register size, whether or not
@@ -21,31 +28,243 @@ pub struct Manifest {
 /// of the input JSON file.
 ///
 /// It contains the name of the peripheral access crate and a list of peripherals.
+/// Peripherals can either be defined inline or in separate files listed in `includes`,
+/// which are resolved relative to the entry file.
 #[derive(Deserialize, Debug)]
 pub struct PeripheralAccess {
     pub name: String,
     pub register_size: usize,
     pub is_no_std: Option<bool>,
+    pub includes: Option<Vec<String>>,
+    #[serde(default)]
     pub peripherals: Vec<Peripheral>,
+    pub memory_regions: Option<Vec<MemoryRegion>>,
+    pub bit_band_regions: Option<Vec<BitBandRegion>>,
+    /// Every file the description was loaded from (the entry file first, then the includes).
+    #[serde(skip)]
+    pub source_files: Vec<PathBuf>,
+}
+
+/// The MemoryRegion struct represents a region of the address map of the device,
+/// such as on-chip memory, DDR or memory-mapped flash.
+///
+/// It contains the name of the region (as used in the linker script), its origin
+/// and length in bytes, optionally the linker attributes (e.g. "rwx"), and a description.
+#[derive(Deserialize, Debug)]
+pub struct MemoryRegion {
+    pub name: String,
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub origin: usize,
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub length: usize,
+    pub attributes: Option<String>,
+    pub description: Option<String>,
+}
+
+/// The BitBandRegion struct represents a Cortex-M bit-band region, where every bit of
+/// the region can be set or cleared atomically through a word in the alias region.
+///
+/// It contains the origin and length of the bit-band region in bytes, and the start
+/// address of its alias region.
+#[derive(Deserialize, Debug)]
+pub struct BitBandRegion {
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub origin: usize,
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub length: usize,
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub alias: usize,
+}
+
+impl BitBandRegion {
+    /// Returns true if the address is inside the bit-band region.
+    pub fn contains(&self, address: usize) -> bool {
+        address >= self.origin && address - self.origin < self.length
+    }
+}
+
+/// The AtomicAliases struct represents the hardware alias registers of a peripheral,
+/// where writing a mask sets, clears or toggles the bits of the mask in the register
+/// atomically (e.g. RP2040-style aliases at +0x1000, +0x2000 and +0x3000).
+///
+/// Each offset is the distance in bytes between a register and its alias.
+#[derive(Deserialize, Debug)]
+pub struct AtomicAliases {
+    #[serde(default, deserialize_with = "utils::deserialize_num_with_option")]
+    pub set: Option<usize>,
+    #[serde(default, deserialize_with = "utils::deserialize_num_with_option")]
+    pub clear: Option<usize>,
+    #[serde(default, deserialize_with = "utils::deserialize_num_with_option")]
+    pub toggle: Option<usize>,
 }
 
 /// The Peripheral struct represents a peripheral device in the input JSON file.
 ///
 /// It contains the name of the peripheral, the base address of the peripheral,
-/// and a list of registers for the device.
+/// a list of registers for the device, and optionally its clusters, the interrupts it
+/// raises and its atomic alias registers.
 #[derive(Deserialize, Debug)]
 pub struct Peripheral {
     pub name: String,
     #[serde(deserialize_with = "utils::deserialize_num")]
     pub base_address: usize,
     pub registers: Vec<Register>,
+    pub clusters: Option<Vec<Cluster>>,
+    pub interrupts: Option<Vec<Interrupt>>,
+    pub atomic_aliases: Option<AtomicAliases>,
+}
+
+impl Peripheral {
+    /// Returns the clusters of the peripheral, or an empty slice if there are none.
+    pub fn clusters(&self) -> &[Cluster] {
+        self.clusters.as_deref().unwrap_or_default()
+    }
+}
+
+/// The Cluster struct represents a group of registers inside a peripheral, such as the
+/// register set of one DMA channel.
+///
+/// It contains the name of the cluster, the offset from the start of the enclosing
+/// peripheral or cluster, a description, the registers and nested clusters of the group,
+/// and optionally a count and stride (in bytes) when the group is repeated as an array.
+#[derive(Deserialize, Debug)]
+pub struct Cluster {
+    pub name: String,
+    #[serde(deserialize_with = "utils::deserialize_num")]
+    pub offset: usize,
+    pub description: Option<String>,
+    pub count: Option<usize>,
+    #[serde(default, deserialize_with = "utils::deserialize_num_with_option")]
+    pub stride: Option<usize>,
+    #[serde(default)]
+    pub registers: Vec<Register>,
+    pub clusters: Option<Vec<Cluster>>,
+}
+
+impl Cluster {
+    /// Returns the nested clusters of the cluster, or an empty slice if there are none.
+    pub fn clusters(&self) -> &[Cluster] {
+        self.clusters.as_deref().unwrap_or_default()
+    }
+
+    /// Calculates the size in bytes of a single element of the cluster.
+    /// For cluster arrays, this is the stride between elements if one is given.
+    /// A stride smaller than the registers of the cluster is ignored, since the elements
+    /// would overlap; the validation reports it.
+    ///
+    /// # Returns
+    /// The size of one element of the cluster in bytes.
+    pub fn element_size(&self) -> usize {
+        let size = block_size(&self.registers, self.clusters());
+        self.stride.map_or(size, |stride| stride.max(size))
+    }
+}
+
+/// Calculates the size in bytes of the members of a register block, from its start
+/// to the end of the member that ends last.
+///
+/// # Arguments
+/// registers: &[Register] - The registers of the block.
+/// clusters: &[Cluster] - The clusters of the block.
+///
+/// # Returns
+/// The size of the register block in bytes.
+pub fn block_size(registers: &[Register], clusters: &[Cluster]) -> usize {
+    let register_ends = registers
+        .iter()
+        .map(|register| register.offset + register.size / 8);
+    let cluster_ends = clusters
+        .iter()
+        .map(|cluster| cluster.offset + cluster.element_size() * cluster.count.unwrap_or(1));
+    register_ends.chain(cluster_ends).max().unwrap_or(0)
+}
+
+/// The FlatRegister struct is a register of a peripheral with the clusters flattened.
+///
+/// It contains the path of the register in the peripheral, such as "MODE" or "CH0.CFG"
+/// for the CFG register of the first element of the CH cluster array, its offset from
+/// the base address of the peripheral, and the register itself.
+#[derive(Debug, Clone)]
+pub struct FlatRegister<'a> {
+    pub path: String,
+    pub offset: usize,
+    pub register: &'a Register,
+}
+
+impl Peripheral {
+    /// Returns every register of the peripheral, including the registers of its clusters
+    /// and of every element of its cluster arrays, sorted by offset.
+    pub fn flat_registers(&self) -> Vec<FlatRegister<'_>> {
+        let mut registers = Vec::new();
+        flatten_registers(&self.registers, self.clusters(), "", 0, &mut registers);
+        registers.sort_by_key(|register| register.offset);
+        registers
+    }
+}
+
+/// Collects the registers of a register block and its clusters into a flat list.
+fn flatten_registers<'a>(
+    registers: &'a [Register],
+    clusters: &'a [Cluster],
+    prefix: &str,
+    base_offset: usize,
+    flat_registers: &mut Vec<FlatRegister<'a>>,
+) {
+    for register in registers {
+        flat_registers.push(FlatRegister {
+            path: format!("{}{}", prefix, register.name),
+            offset: base_offset + register.offset,
+            register,
+        });
+    }
+
+    for cluster in clusters {
+        match cluster.count {
+            Some(count) => {
+                let element_size = cluster.element_size();
+                for i in 0..count {
+                    flatten_registers(
+                        &cluster.registers,
+                        cluster.clusters(),
+                        &format!("{}{}{}.", prefix, cluster.name, i),
+                        base_offset + cluster.offset + i * element_size,
+                        flat_registers,
+                    );
+                }
+            }
+            None => flatten_registers(
+                &cluster.registers,
+                cluster.clusters(),
+                &format!("{}{}.", prefix, cluster.name),
+                base_offset + cluster.offset,
+                flat_registers,
+            ),
+        }
+    }
+}
+
+/// The Interrupt struct represents an interrupt raised by a peripheral.
+///
+/// It contains the name of the interrupt, its number in the interrupt controller,
+/// and a description. Interrupts of different peripherals can share a number (a shared
+/// interrupt line), in which case they share the `Interrupt` variant of the first one.
+#[derive(Deserialize, Debug)]
+pub struct Interrupt {
+    pub name: String,
+    pub number: u16,
+    pub description: String,
+    /// The name of the first interrupt of the description with the same number, if this
+    /// interrupt is not the first one
+    #[serde(skip)]
+    pub shared_with: Option<String>,
 }
 
 /// The Register struct represents a register in a peripheral device.
 ///
 /// It contains the name of the register, the offset from the base address,
 /// the size of the register in bits, the access type of the register, a description,
-/// and a list of fields in the register.
+/// the value of the register after reset, a list of fields in the register, and whether
+/// reading it has side effects.
 #[derive(Deserialize, Debug)]
 pub struct Register {
     pub name: String,
@@ -55,7 +274,12 @@ pub struct Register {
     #[serde(deserialize_with = "utils::deserialize_access")]
     pub access: Access,
     pub description: String,
+    /// The value of the register after reset, if the description declares it
+    #[serde(default, deserialize_with = "utils::deserialize_num_with_option")]
+    pub reset_value: Option<usize>,
     pub fields: Option<Vec<Field>>,
+    /// Whether a read changes the state of the peripheral, e.g. pops a FIFO or clears flags
+    pub read_side_effects: Option<bool>,
 }
 
 /// The Field struct represents a field in a register.
@@ -75,6 +299,92 @@ pub struct Field {
     pub enums: Option<Enum>,
 }
 
+impl Register {
+    /// Returns the value of the register after reset: the declared reset value if there is
+    /// one, or else the value built from the default values of its fields, the same way as
+    /// the `Default` impl of the generated register builder.
+    pub fn reset_value(&self) -> u64 {
+        if let Some(reset_value) = self.reset_value {
+            return reset_value as u64;
+        }
+
+        self.fields
+            .iter()
+            .flatten()
+            .fold(0, |value, field| match field.default_bits() {
+                Some(bits) => field.insert(value, bits),
+                None => value,
+            })
+    }
+
+    /// Returns the value of a field of the register after reset, not shifted to its
+    /// position: taken from the declared reset value of the register if there is one, or
+    /// else the default of the field.
+    pub fn field_reset(&self, field: &Field) -> Option<u64> {
+        match self.reset_value {
+            Some(reset_value) => Some(field.extract(reset_value as u64)),
+            None => field.default_bits(),
+        }
+    }
+}
+
+impl Field {
+    /// Returns the mask of the field, shifted to its position in the register.
+    pub fn mask(&self) -> u64 {
+        let (start, end) = self.bit_range.get_bit_range();
+        // Bits beyond the 64 bits of the value are left out instead of overflowing
+        let width = (end + 1).saturating_sub(start).min(64);
+        let bits = u64::MAX.checked_shr(64 - width as u32).unwrap_or(0);
+        u32::try_from(start)
+            .ok()
+            .and_then(|start| bits.checked_shl(start))
+            .unwrap_or(0)
+    }
+
+    /// Returns the value of the field after reset, not shifted to its position.
+    ///
+    /// It is the default value of the field if it has one, or else the value of the
+    /// enum variant marked as default, or else the value of the first variant.
+    pub fn default_bits(&self) -> Option<u64> {
+        if let Some(default_value) = self.default_value {
+            if self.bit_range.is_single_bit() {
+                Some((default_value != 0) as u64)
+            } else {
+                Some(default_value as u64)
+            }
+        } else {
+            self.enums.as_ref().and_then(|enum_def| {
+                enum_def
+                    .values
+                    .iter()
+                    .find(|v| v.is_default.unwrap_or(false))
+                    .or(enum_def.values.first())
+                    .map(|v| v.value as u64)
+            })
+        }
+    }
+
+    /// Extracts the value of the field from a register value.
+    pub fn extract(&self, register_value: u64) -> u64 {
+        let start = self.bit_range.get_start_bit();
+        u32::try_from(start)
+            .ok()
+            .and_then(|start| (register_value & self.mask()).checked_shr(start))
+            .unwrap_or(0)
+    }
+
+    /// Replaces the value of the field in a register value. The bits that do not fit in
+    /// the field are dropped.
+    pub fn insert(&self, register_value: u64, bits: u64) -> u64 {
+        let start = self.bit_range.get_start_bit();
+        let shifted = u32::try_from(start)
+            .ok()
+            .and_then(|start| bits.checked_shl(start))
+            .unwrap_or(0);
+        (register_value & !self.mask()) | (shifted & self.mask())
+    }
+}
+
 /// The Enum struct represents an enumeration value for a field.
 ///
 /// It contains the name of the enumeration, the value of the enumeration,
@@ -87,6 +397,7 @@ pub struct Field {
 #[derive(Deserialize, Debug)]
 pub struct Enum {
     pub name: String,
+    #[serde(deserialize_with = "utils::deserialize_non_empty")]
     pub values: Vec<EnumValue>,
 }
 
@@ -100,15 +411,87 @@ pub struct EnumValue {
     pub is_default: Option<bool>,
 }
 
-/// Parses the input JSON string into a PeripheralAccess struct.
+/// Parses the input JSON file into a PeripheralAccess struct.
+///
+/// Any files listed in `includes` are parsed as single peripherals and appended
+/// to the list of peripherals, in the order they are listed.
 ///
 /// # Arguments
-/// input: &PathBuf - The path to the input JSON file.
+/// input: &Path - The path to the input JSON file.
 ///
 /// # Returns
 /// A Result containing the parsed PeripheralAccess struct.
-pub fn parse_input(input: &PathBuf) -> Result<PeripheralAccess, serde_json::Error> {
-    let input_file = std::fs::File::open(input).unwrap();
+pub fn parse_input(input: &Path) -> Result<PeripheralAccess, Error> {
+    let mut pac: PeripheralAccess = parse_json_file(input)?;
+    pac.source_files.push(input.to_path_buf());
+
+    let base_dir = input.parent().unwrap_or(Path::new("."));
+    for include in pac.includes.clone().unwrap_or_default() {
+        let include_path = base_dir.join(include);
+        let peripheral: Peripheral = parse_json_file(&include_path)?;
+        pac.peripherals.push(peripheral);
+        pac.source_files.push(include_path);
+    }
+
+    link_shared_interrupts(&mut pac);
+    Ok(pac)
+}
+
+/// Links every interrupt to the first interrupt of the description with the same number.
+fn link_shared_interrupts(pac: &mut PeripheralAccess) {
+    let mut first_names: HashMap<u16, String> = HashMap::new();
+    let interrupts = pac
+        .peripherals
+        .iter_mut()
+        .flat_map(|peripheral| peripheral.interrupts.iter_mut().flatten());
+    for interrupt in interrupts {
+        match first_names.get(&interrupt.number) {
+            Some(name) => interrupt.shared_with = Some(name.clone()),
+            None => {
+                first_names.insert(interrupt.number, interrupt.name.clone());
+            }
+        }
+    }
+}
+
+/// Lists the files a description is loaded from, the entry file and its includes, even
+/// when the description does not parse. Only the `includes` of the entry file are read.
+///
+/// # Arguments
+/// input: &Path - The path to the input JSON file.
+///
+/// # Returns
+/// The entry file, followed by the included files that could be found.
+pub fn list_source_files(input: &Path) -> Vec<PathBuf> {
+    /// The part of the entry file that lists the includes
+    #[derive(Deserialize)]
+    struct Includes {
+        includes: Option<Vec<String>>,
+    }
+
+    let base_dir = input.parent().unwrap_or(Path::new("."));
+    let includes = parse_json_file::<Includes>(input)
+        .ok()
+        .and_then(|entry| entry.includes)
+        .unwrap_or_default();
+
+    std::iter::once(input.to_path_buf())
+        .chain(includes.iter().map(|include| base_dir.join(include)))
+        .collect()
+}
+
+/// Parses a single JSON file into any deserializable type.
+///
+/// # Arguments
+/// path: &Path - The path to the JSON file.
+///
+/// # Returns
+/// A Result containing the parsed value.
+fn parse_json_file<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, Error> {
+    let input_file = std::fs::File::open(path).map_err(|e| Error::io(path, e))?;
     let input_reader = std::io::BufReader::new(input_file);
-    serde_json::from_reader(input_reader)
+    serde_json::from_reader(input_reader).map_err(|e| Error::Json {
+        path: path.to_path_buf(),
+        source: e,
+    })
 }
@@ -1,6 +1,6 @@
-use serde::{Deserialize, Deserializer};
+//! The parse::utils module contains utility functions for post-processing the parsed data.
 
-/// The parse::utils module contains utility functions for post-processing the parsed data.
+use serde::{de::Error, Deserialize, Deserializer};
 
 /// The BitRange enum represents a range of bits in a register field.
 #[derive(Debug)]
@@ -16,6 +16,20 @@ impl BitRange {
     /// bit_range: &str - The string representation of the bit range. It is in the format
     ///                   of "[start:end]" or "[bit]".
     pub fn from_string(bit_range: &str) -> BitRange {
+        BitRange::try_from_string(bit_range)
+            .unwrap_or_else(|| panic!("Invalid bit range: {}", bit_range))
+    }
+
+    /// Parses a string representation of a bit range, like `from_string`, but without
+    /// panicking on invalid input.
+    ///
+    /// # Arguments
+    /// bit_range: &str - The string representation of the bit range.
+    ///
+    /// # Returns
+    /// The BitRange, or None if the string is not a valid bit range or its high bit
+    /// comes after its low bit, as in "[1:2]".
+    pub fn try_from_string(bit_range: &str) -> Option<BitRange> {
         // Remove the brackets from the bit range string
         let bit_range = bit_range.trim_matches(|c| c == '[' || c == ']');
 
@@ -24,24 +38,22 @@ impl BitRange {
 
         // Match the number of parts to determine the type of bit range
         match parts.len() {
-            1 => BitRange::Single(parts[0].parse().unwrap()),
-            2 => BitRange::Range(parts[1].parse().unwrap(), parts[0].parse().unwrap()),
-            _ => panic!("Invalid bit range: {}", bit_range),
+            1 => Some(BitRange::Single(parts[0].trim().parse().ok()?)),
+            2 => {
+                let end: usize = parts[0].trim().parse().ok()?;
+                let start: usize = parts[1].trim().parse().ok()?;
+                (start <= end).then_some(BitRange::Range(start, end))
+            }
+            _ => None,
         }
     }
 
     pub fn is_single_bit(&self) -> bool {
-        match self {
-            BitRange::Single(_) => true,
-            _ => false,
-        }
+        matches!(self, BitRange::Single(_))
     }
 
     pub fn is_bit_range(&self) -> bool {
-        match self {
-            BitRange::Range(_, _) => true,
-            _ => false,
-        }
+        matches!(self, BitRange::Range(_, _))
     }
 
     pub fn get_start_bit(&self) -> usize {
@@ -87,7 +99,12 @@ where
     D: Deserializer<'de>,
 {
     let bit_range: String = Deserialize::deserialize(deserializer)?;
-    Ok(BitRange::from_string(&bit_range))
+    BitRange::try_from_string(&bit_range).ok_or_else(|| {
+        D::Error::custom(format!(
+            "invalid bit range: {}, expected [bit] or [high:low]",
+            bit_range
+        ))
+    })
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -112,12 +129,34 @@ impl Access {
     /// # Returns
     /// The Access enum corresponding to the access type.
     pub fn from_string(access: &str) -> Access {
+        Access::try_from_string(access).unwrap_or_else(|| panic!("Invalid access type: {}", access))
+    }
+
+    /// Parses a string representation of an access type, like `from_string`, but without
+    /// panicking on invalid input.
+    ///
+    /// # Arguments
+    /// access: &str - The string representation of the access type.
+    ///
+    /// # Returns
+    /// The Access enum, or None if the string is not a valid access type.
+    pub fn try_from_string(access: &str) -> Option<Access> {
         match access {
-            "read-only" => Access::ReadOnly,
-            "write-only" => Access::WriteOnly,
-            "read-write" => Access::ReadWrite,
-            "write-to-clear" => Access::WriteToClear,
-            _ => panic!("Invalid access type: {}", access),
+            "read-only" => Some(Access::ReadOnly),
+            "write-only" => Some(Access::WriteOnly),
+            "read-write" => Some(Access::ReadWrite),
+            "write-to-clear" => Some(Access::WriteToClear),
+            _ => None,
+        }
+    }
+
+    /// Returns the string representation of the access type, as written in the input file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Access::ReadOnly => "read-only",
+            Access::WriteOnly => "write-only",
+            Access::ReadWrite => "read-write",
+            Access::WriteToClear => "write-to-clear",
         }
     }
 }
@@ -141,7 +180,7 @@ where
     D: Deserializer<'de>,
 {
     let access: String = Deserialize::deserialize(deserializer)?;
-    Ok(Access::from_string(&access))
+    parse_access::<D>(&access)
 }
 
 /// Deserializes an access type from a string representation.
@@ -163,10 +202,13 @@ where
     D: Deserializer<'de>,
 {
     let access: Option<String> = Deserialize::deserialize(deserializer)?;
-    match access {
-        Some(access) => Ok(Some(Access::from_string(&access))),
-        None => Ok(None),
-    }
+    access.map(|access| parse_access::<D>(&access)).transpose()
+}
+
+/// Parses an access type for a deserializer, with an error instead of a panic.
+fn parse_access<'de, D: Deserializer<'de>>(access: &str) -> Result<Access, D::Error> {
+    Access::try_from_string(access)
+        .ok_or_else(|| D::Error::custom(format!("invalid access type: {}", access)))
 }
 
 /// Parses a hexadecimal string into a usize.
@@ -225,6 +267,28 @@ pub fn parse_num_string(num_string: &str) -> usize {
     }
 }
 
+/// Parses a number string into a u64, like `parse_num_string`, but without panicking
+/// on invalid input. Used for the values given on the command line.
+///
+/// # Arguments
+/// num_string: &str - The number string to parse.
+///
+/// # Returns
+/// The parsed u64 value, or None if the string is not a valid number.
+pub fn try_parse_num_string(num_string: &str) -> Option<u64> {
+    let num_string = num_string.trim().replace('_', "");
+    if let Some(hex) = num_string
+        .strip_prefix("0x")
+        .or(num_string.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = num_string.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2).ok()
+    } else {
+        num_string.parse().ok()
+    }
+}
+
 /// Deserializes a number from a string representation.
 ///
 /// The number can be in the following formats:
@@ -242,7 +306,7 @@ where
     D: Deserializer<'de>,
 {
     let num_string: String = Deserialize::deserialize(deserializer)?;
-    Ok(parse_num_string(&num_string))
+    parse_num::<D>(&num_string)
 }
 
 /// Deserializes a number from a string representation.
@@ -262,8 +326,33 @@ where
     D: Deserializer<'de>,
 {
     let num_string: Option<String> = Deserialize::deserialize(deserializer)?;
-    match num_string {
-        Some(num_string) => Ok(Some(parse_num_string(&num_string))),
-        None => Ok(None),
+    num_string
+        .map(|num_string| parse_num::<D>(&num_string))
+        .transpose()
+}
+
+/// Parses a number for a deserializer, with an error instead of a panic.
+fn parse_num<'de, D: Deserializer<'de>>(num_string: &str) -> Result<usize, D::Error> {
+    try_parse_num_string(num_string)
+        .and_then(|num| usize::try_from(num).ok())
+        .ok_or_else(|| D::Error::custom(format!("invalid number: {}", num_string)))
+}
+
+/// Deserializes a list that must have at least one element, such as the values of an enum.
+///
+/// # Arguments
+/// deserializer: D - The deserializer for the list.
+///
+/// # Returns
+/// A Result containing the deserialized list.
+pub fn deserialize_non_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let values: Vec<T> = Deserialize::deserialize(deserializer)?;
+    if values.is_empty() {
+        return Err(D::Error::invalid_length(0, &"at least one element"));
     }
+    Ok(values)
 }
@@ -0,0 +1,207 @@
+//! The parse::validate module checks a parsed description for mistakes that still
+//! deserialize fine, such as overlapping registers or a field that does not fit in its
+//! register, so they are reported before the generated code fails to compile or
+//! silently accesses the wrong bits.
+
+use std::{collections::HashSet, fmt};
+
+use super::{block_size, Cluster, PeripheralAccess, Register};
+
+/// The Diagnostic struct is a problem found in the description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The path of the item with the problem, e.g. "UART.MODE.char_len"
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// The number of external interrupts of the Cortex-M NVIC, the size of the largest vector table.
+const MAX_INTERRUPTS: u16 = 496;
+
+/// Validates a parsed description.
+///
+/// # Arguments
+/// pac: &PeripheralAccess - The parsed peripheral access data structure.
+///
+/// # Returns
+/// The problems found, in the order of the description. An empty list means the
+/// description is valid.
+pub fn validate(pac: &PeripheralAccess) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut push = |path: &str, message: String| {
+        diagnostics.push(Diagnostic {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    let mut peripheral_names = HashSet::new();
+    for peripheral in &pac.peripherals {
+        if !peripheral_names.insert(&peripheral.name) {
+            push(&peripheral.name, "duplicate peripheral name".to_string());
+        }
+
+        validate_clusters(&peripheral.name, peripheral.clusters(), &mut push);
+
+        for interrupt in peripheral.interrupts.iter().flatten() {
+            let path = format!("{}.{}", peripheral.name, interrupt.name);
+            if interrupt.number >= MAX_INTERRUPTS {
+                push(
+                    &path,
+                    format!(
+                        "interrupt number {} is beyond the {} interrupts of the NVIC",
+                        interrupt.number, MAX_INTERRUPTS
+                    ),
+                );
+            }
+            if let Some(first) = interrupt
+                .shared_with
+                .as_ref()
+                .filter(|first| **first != interrupt.name)
+            {
+                push(
+                    &path,
+                    format!(
+                        "shares interrupt number {} with {}, whose Interrupt variant it uses",
+                        interrupt.number, first
+                    ),
+                );
+            }
+        }
+
+        // The registers of cluster arrays are only checked once
+        let mut checked = HashSet::new();
+        let mut previous: Option<(String, usize)> = None;
+        for flat_register in peripheral.flat_registers() {
+            let path = format!("{}.{}", peripheral.name, flat_register.path);
+            let register = flat_register.register;
+
+            if let Some((previous_path, previous_end)) = &previous {
+                if flat_register.offset < *previous_end {
+                    push(&path, format!("overlaps {}", previous_path));
+                }
+            }
+            let end = flat_register.offset + register.size / 8;
+            if previous
+                .as_ref()
+                .is_none_or(|(_, previous_end)| end > *previous_end)
+            {
+                previous = Some((path.clone(), end));
+            }
+
+            if checked.insert(register as *const Register) {
+                validate_register(&path, register, &mut push);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Validates the strides of clusters and their nested clusters.
+fn validate_clusters(path: &str, clusters: &[Cluster], push: &mut impl FnMut(&str, String)) {
+    for cluster in clusters {
+        let cluster_path = format!("{}.{}", path, cluster.name);
+        let size = block_size(&cluster.registers, cluster.clusters());
+        if let Some(stride) = cluster.stride.filter(|stride| *stride < size) {
+            push(
+                &cluster_path,
+                format!(
+                    "stride of {} bytes is smaller than the {} bytes of its registers",
+                    stride, size
+                ),
+            );
+        }
+        validate_clusters(&cluster_path, cluster.clusters(), push);
+    }
+}
+
+/// Validates the fields of a register.
+fn validate_register(path: &str, register: &Register, push: &mut impl FnMut(&str, String)) {
+    if !matches!(register.size, 8 | 16 | 32 | 64) {
+        push(
+            path,
+            format!("unsupported register size of {} bits", register.size),
+        );
+    }
+    if let Some(reset_value) = register.reset_value {
+        if register.size < usize::BITS as usize && reset_value >> register.size != 0 {
+            push(
+                path,
+                format!(
+                    "reset value 0x{:X} does not fit in {} bits",
+                    reset_value, register.size
+                ),
+            );
+        }
+    }
+
+    let fields = register.fields.as_deref().unwrap_or_default();
+    let mut names = HashSet::new();
+    for (i, field) in fields.iter().enumerate() {
+        let field_path = format!("{}.{}", path, field.name);
+        let (start, end) = field.bit_range.get_bit_range();
+        let Some(width) = (end + 1).checked_sub(start) else {
+            push(
+                &field_path,
+                format!("bit range [{}:{}] is reversed", end, start),
+            );
+            continue;
+        };
+
+        if !names.insert(&field.name) {
+            push(&field_path, "duplicate field name".to_string());
+        }
+        if end >= register.size {
+            push(
+                &field_path,
+                format!(
+                    "bit {} is outside of the {}-bit register",
+                    end, register.size
+                ),
+            );
+        }
+        if let Some(other) = fields[..i].iter().find(|other| {
+            let (other_start, other_end) = other.bit_range.get_bit_range();
+            start <= other_end && other_start <= end
+        }) {
+            push(&field_path, format!("overlaps field {}", other.name));
+        }
+
+        let fits = |value: usize| width >= usize::BITS as usize || value >> width == 0;
+        if let Some(default_value) = field.default_value {
+            if !fits(default_value) {
+                push(
+                    &field_path,
+                    format!(
+                        "default value 0x{:X} does not fit in {} bits",
+                        default_value, width
+                    ),
+                );
+            }
+        }
+
+        let mut values = HashSet::new();
+        for value in field.enums.iter().flat_map(|enum_def| &enum_def.values) {
+            let value_path = format!("{}.{}", field_path, value.name);
+            if !fits(value.value) {
+                push(
+                    &value_path,
+                    format!("value 0x{:X} does not fit in {} bits", value.value, width),
+                );
+            }
+            if !values.insert(value.value) {
+                push(
+                    &value_path,
+                    format!("value 0x{:X} is used by another variant", value.value),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! The watch module detects changes of the source files of a generated crate by polling
+//! their modification times. Polling needs no platform specific file system events, and
+//! a description is only a handful of files.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// The Snapshot struct holds the modification times of a set of files. A file that does
+/// not exist has no modification time, so creating or deleting it counts as a change.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// Reads the modification time of a file.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the given files.
+    ///
+    /// # Arguments
+    /// paths: &[PathBuf] - The files to watch.
+    ///
+    /// # Returns
+    /// The snapshot of the current modification times.
+    pub fn take(paths: &[PathBuf]) -> Self {
+        let mut snapshot = Snapshot::default();
+        for path in paths {
+            snapshot.track(path);
+        }
+        snapshot
+    }
+
+    /// Adds a file to the snapshot if it is not in it yet.
+    ///
+    /// # Arguments
+    /// path: &Path - The file to watch.
+    pub fn track(&mut self, path: &Path) {
+        if !self.files.iter().any(|(tracked, _)| tracked == path) {
+            self.files.push((path.to_path_buf(), modified(path)));
+        }
+    }
+
+    /// Lists the files that changed since the snapshot was taken.
+    ///
+    /// # Returns
+    /// The paths of the changed files.
+    pub fn changed(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(path, time)| modified(path) != *time)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Blocks until at least one of the files changes.
+    ///
+    /// # Arguments
+    /// interval: Duration - How often the files are checked.
+    ///
+    /// # Returns
+    /// The paths of the changed files.
+    pub fn wait_for_change(&self, interval: Duration) -> Vec<PathBuf> {
+        loop {
+            thread::sleep(interval);
+            if !self.changed().is_empty() {
+                // Editors often save in several steps, so let them finish first
+                thread::sleep(interval);
+                return self.changed();
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Generates the zynq crate with its `linux` feature and maps a regular file as a stand-in
+//! for the device, through a small program built against the generated crate.
+
+#![cfg(target_os = "linux")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use rust_pac_gen::Generator;
+
+/// The offset of the UART in the mapped file, on purpose not aligned to a page.
+const UART_OFFSET: usize = 0x1004;
+
+/// The offset of the BAUD_GEN register in the UART.
+const BAUD_GEN_OFFSET: usize = 0x18;
+
+/// The program that maps the file given as its first argument at UART_OFFSET, writes
+/// the divisor given as its second argument to BAUD_GEN and prints the outcome.
+const MAIN_RS: &str = r#"
+use zynq_pac::{uart, RegisterWO};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let cd = args[2].parse().unwrap();
+    match uart::RegisterBlock::map_file(&args[1], 0x1004) {
+        Ok(mut uart) => {
+            uart.baud_gen.write(uart::baud_gen::BaudGenBuilder::default().with_cd(cd));
+            println!("ok");
+        }
+        Err(e) => println!("{:?}", e.kind()),
+    }
+}
+"#;
+
+/// Generates the crate and builds the program once, and returns the path of the program.
+fn harness() -> &'static Path {
+    static HARNESS: OnceLock<PathBuf> = OnceLock::new();
+    HARNESS.get_or_init(|| {
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("linux-harness");
+        let _ = fs::remove_dir_all(&dir);
+
+        Generator::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/zynq-700.json"))
+            .with_crate_name("zynq_pac")
+            .with_output_dir(dir.join("zynq_pac"))
+            .generate()
+            .unwrap();
+
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"harness\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nzynq_pac = { path = \"zynq_pac\", features = [\"linux\"] }\n\n\
+             [workspace]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/main.rs"), MAIN_RS).unwrap();
+
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let status = Command::new(cargo)
+            .args(["build", "--quiet"])
+            .current_dir(&dir)
+            .env("CARGO_TARGET_DIR", dir.join("target"))
+            .status()
+            .unwrap();
+        assert!(status.success(), "the harness did not build");
+
+        dir.join("target/debug/harness")
+    })
+}
+
+/// Runs the program on a file and returns what it printed.
+fn run(file: &Path, cd: u16) -> String {
+    let output = Command::new(harness())
+        .arg(file)
+        .arg(cd.to_string())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn map_file_writes_registers_at_an_unaligned_offset() {
+    let file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("linux-regs.bin");
+    fs::write(&file, vec![0xAA; 0x2000]).unwrap();
+
+    assert_eq!(run(&file, 0x1234), "ok");
+
+    let bytes = fs::read(&file).unwrap();
+    let register = UART_OFFSET + BAUD_GEN_OFFSET;
+    assert_eq!(bytes[register..register + 4], 0x1234u32.to_le_bytes());
+    // Nothing around the register is touched
+    assert!(bytes[..register].iter().all(|&b| b == 0xAA));
+    assert!(bytes[register + 4..].iter().all(|&b| b == 0xAA));
+}
+
+#[test]
+fn map_file_fails_on_a_file_smaller_than_the_register_block() {
+    let file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("linux-short.bin");
+    fs::write(&file, vec![0xAA; UART_OFFSET + BAUD_GEN_OFFSET]).unwrap();
+
+    assert_eq!(run(&file, 0x1234), "UnexpectedEof");
+    assert!(fs::read(&file).unwrap().iter().all(|&b| b == 0xAA));
+}